@@ -0,0 +1,99 @@
+use serde::de::Error;
+use std::{fs, path};
+
+use crate::{shortcut::Shortcut, controller::Controller, errors::{Result, ScuError}, resource::Resource, migration};
+
+const SHORTCUTS_KEY: &str = "shortcuts";
+
+/// Templates shared as a single file, they are written in the current version and upgraded when loaded
+#[derive(Debug)]
+pub struct Bundle {
+  pub shortcuts: Vec<Shortcut>
+}
+
+impl Bundle {
+  /// Directory resources are embedded, the bundle does not depend on the files of this machine
+  pub fn new(shortcuts: impl IntoIterator<Item = Shortcut>) -> Result<Self> {
+    let shortcuts = shortcuts.into_iter().map(|mut shortcut| {
+      shortcut.update_startup_reference(None);
+      shortcut.resources = shortcut.resources.map(
+        |resources| resources.iter().map(Resource::embedded).collect::<Result<Vec<Resource>>>()
+      ).transpose()?;
+      Ok(shortcut)
+    }).collect::<Result<Vec<Shortcut>>>()?;
+    Ok(Bundle { shortcuts })
+  }
+
+  /// Reads a bundle of any known version, bundles written before versioning have version 0
+  pub fn load(path: impl AsRef<path::Path>) -> Result<Self> {
+    let mut table: toml::Table = toml::from_str(fs::read_to_string(path)?.as_str())?;
+    let version = migration::version(&table)?;
+    let shortcuts = match table.remove(SHORTCUTS_KEY) {
+      Some(toml::Value::Array(shortcuts)) => shortcuts,
+      None => Vec::new(),
+      Some(_) => return Err(toml::de::Error::custom("shortcuts must be an array of tables").into())
+    };
+    let shortcuts = shortcuts.into_iter().map(|shortcut| match shortcut {
+      toml::Value::Table(mut shortcut) => {
+        shortcut.insert(migration::VERSION_KEY.to_string(), toml::Value::Integer(version.into()));
+        migration::from_table(shortcut).map(|(shortcut, _)| shortcut).map_err(ScuError::from)
+      },
+      _ => Err(toml::de::Error::custom("shortcuts must be an array of tables").into())
+    }).collect::<Result<Vec<Shortcut>>>()?;
+    Ok(Bundle { shortcuts })
+  }
+
+  pub fn store(&self, path: impl AsRef<path::Path>) -> Result<()> {
+    let shortcuts = self.shortcuts.iter().map(|shortcut| toml::Table::try_from(shortcut).map(toml::Value::Table))
+      .collect::<std::result::Result<Vec<toml::Value>, toml::ser::Error>>()?;
+    let table = toml::Table::from_iter([
+      (migration::VERSION_KEY.to_string(), toml::Value::Integer(migration::CURRENT_VERSION.into())),
+      (SHORTCUTS_KEY.to_string(), toml::Value::Array(shortcuts))
+    ]);
+    toml::to_string_pretty(&table).map_err(ScuError::from)
+      .and_then(|data| fs::write(path, data).map_err(|err| err.into()))
+  }
+
+  pub fn localize(self, controller: &Controller, prefix: Option<&str>) -> Result<Vec<Shortcut>> {
+    let prefix = prefix.unwrap_or_default();
+    self.shortcuts.into_iter().map(|mut shortcut| {
+      shortcut.name = format!("{}{}", prefix, shortcut.name);
+      shortcut.aliases = shortcut.aliases.map(
+        |aliases| aliases.into_iter().map(|alias| format!("{}{}", prefix, alias)).collect()
+      );
+      controller.localize_resources(&mut shortcut)?;
+      Ok(shortcut)
+    }).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  #[test]
+  fn bundles_are_stored_with_their_version() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("bundle.toml");
+    let shortcut = Shortcut::builder().name("greet").command(vec!["echo".into(), "hello".into()]).build();
+    Bundle::new([shortcut]).unwrap().store(&path).unwrap();
+    assert!(fs::read_to_string(&path).unwrap().starts_with(&format!("version = {}\n", migration::CURRENT_VERSION)));
+    let loaded = Bundle::load(&path).unwrap();
+    assert_eq!(loaded.shortcuts[0].name, "greet");
+    assert_eq!(loaded.shortcuts[0].command(), vec!["echo", "hello"]);
+  }
+
+  #[test]
+  fn unversioned_bundles_are_upgraded() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("bundle.toml");
+    let script = "/data/scu_data/res/report.py";
+    fs::write(&path, format!(
+      "[[shortcuts]]\nname = \"report\"\n\n[shortcuts.body]\ntype = \"CommandWithScript\"\n\n\
+      [shortcuts.body.command]\ncmd = [\"python\"]\nscript = \"{}\"\nbody = \"print('report')\\n\"\n", script
+    )).unwrap();
+    let loaded = Bundle::load(&path).unwrap();
+    assert_eq!(loaded.shortcuts[0].command().last().unwrap(), "/data/scu_data/res/report/report.py");
+  }
+}
@@ -1,6 +1,24 @@
-use clap::{Parser, Subcommand};
+use std::fs;
 
-use crate::{controller::Controller, shortcut::{Shortcut, ShortcutBuilder, ShortcutFile}, errors::Result, interpreter::Interpreter, reader, paths};
+use clap::{Args, Parser, Subcommand};
+
+use scu::{
+  controller::Controller,
+  shortcut::{Shortcut, ShortcutBuilder, ShortcutFile, ShortcutFilter},
+  errors::{Result, ScuError},
+  interpreter::Interpreter,
+  importer::{self, ImportSource},
+  bundle::Bundle,
+  doctor::{self, Status},
+  profile::ProfileFile,
+  script::Shell,
+  streams::Streams,
+  reader,
+  history,
+  paths
+};
+
+use crate::output;
 
 #[derive(Debug, Parser)]
 pub struct Cli {
@@ -10,7 +28,7 @@ pub struct Cli {
 
 #[derive(Debug, Subcommand)]
 pub enum Command {
-  #[clap(about = "Create a shortcut template, it can overwrite an existing one")]
+  #[clap(about = "Create a shortcut template, it can overwrite an existing one with --force")]
   Set {
     name: String,
     #[arg(required = true)]
@@ -23,9 +41,55 @@ pub enum Command {
     file: bool,
     #[arg(short, num_args(0..))]
     interpreters: Option<Vec<String>>,
+    #[arg(short, long = "alias")]
+    aliases: Vec<String>,
+    #[arg(short, long)]
+    description: Option<String>,
+    #[arg(short, long = "tag")]
+    tags: Vec<String>,
+    #[arg(short, long, default_value_t = false)]
+    make: bool,
+    #[arg(long, default_value_t = false)]
+    force: bool,
+    #[command(flatten)]
+    streams: StreamArgs,
+    #[arg(long, help = "Seconds an attempt can run before it is killed")]
+    timeout: Option<u64>,
+    #[arg(long, help = "Attempts to make after a failed one, waiting longer before each")]
+    retries: Option<u32>,
+    #[arg(short, long = "resource", help = "File to embed or directory to copy as resource, referenced as {res:<name>}")]
+    resources: Vec<String>
+  },
+  #[clap(about = "Edit a shortcut template with the default editor")]
+  Edit {
+    name: String,
     #[arg(short, long, default_value_t = false)]
     make: bool
   },
+  #[clap(about = "Import shortcut templates from shell aliases or a binclude installation")]
+  Import {
+    #[arg(long, value_enum)]
+    from: ImportSource,
+    source: String,
+    #[arg(short, num_args(0..))]
+    interpreters: Option<Vec<String>>,
+    #[arg(short, long, default_value_t = false)]
+    overwrite: bool,
+    #[arg(short, long, default_value_t = false)]
+    make: bool,
+    #[arg(long, default_value_t = false, help = "Import shortcuts shadowing existing commands")]
+    force: bool
+  },
+  #[clap(about = "Share shortcut templates as a single bundle file")]
+  Bundle {
+    #[clap(subcommand)]
+    command: BundleCommand
+  },
+  #[clap(about = "Synchronise the shortcut templates and resources with a git remote")]
+  Sync {
+    #[clap(subcommand)]
+    command: SyncCommand
+  },
   #[clap(about = "Delete a shortcut template")]
   Unset {
     #[arg(required = true)]
@@ -36,14 +100,26 @@ pub enum Command {
   #[clap(alias = "rn", about = "Updates a shortcut name")]
   Rename {
     name: String,
-    new_name: String
+    new_name: String,
+    #[arg(short, long, default_value_t = false)]
+    force: bool
   },
   #[clap(about = "List all the existing resources", alias = "ls")]
   List {
     #[arg(short, long)]
     errors: bool,
     #[arg(short, long)]
-    verbose: bool
+    verbose: bool,
+    #[arg(short, long = "tag")]
+    tags: Vec<String>,
+    #[arg(short, long)]
+    grep: Option<String>,
+    #[arg(short, long)]
+    interpreter: Option<String>,
+    #[arg(short, long)]
+    startup: bool,
+    #[arg(short, long)]
+    columns: bool
   },
   #[clap(about = "Generate executable scripts for the desired interpreters")]
   Make {
@@ -54,12 +130,28 @@ pub enum Command {
     #[arg(short)]
     all: bool,
     #[arg(short)]
-    clean: bool
+    clean: bool,
+    #[arg(short, long, default_value_t = false)]
+    force: bool
+  },
+  #[clap(about = "Export shortcuts as definitions to be sourced from a shell profile")]
+  Export {
+    #[arg(long = "as", value_enum)]
+    shell: Shell,
+    #[arg(required = false)]
+    names: Vec<String>,
+    #[arg(short)]
+    output: Option<String>
   },
   #[clap(about = "Clean all the created binaries")]
   Clean,
   #[clap(about = "Returns the binaries directory")]
   Bin,
+  #[clap(about = "Admin the presence of the binaries directory in the PATH")]
+  Path {
+    #[clap(subcommand)]
+    command: PathCommand
+  },
   #[clap(about = "Admin the startup configuration depending on the system")]
   Startup {
     #[arg(required = false)]
@@ -69,52 +161,272 @@ pub enum Command {
     #[arg(short)]
     force: bool,
   },
+  #[clap(about = "Show the recorded changes of the shortcut templates")]
+  History {
+    name: Option<String>
+  },
+  #[clap(about = "Show how often each shortcut was run and failed, according to the execution log")]
+  Stats {
+    #[arg(long, help = "Only show the shortcuts that did not run in the given number of days")]
+    idle: Option<u64>
+  },
+  #[clap(about = "Revert the last recorded change")]
+  Undo,
+  #[clap(about = "Restore a shortcut template to its state before a recorded change")]
+  Restore {
+    name: String,
+    #[arg(long)]
+    at: u64
+  },
+  #[clap(about = "Rewrite the shortcut templates stored in older versions, keeping a backup of each")]
+  Migrate,
+  #[clap(about = "Check the installation and every shortcut for problems")]
+  Doctor,
   #[clap(about = "Run a shortcut as a system command")]
   Run {
     name: String,
     #[arg(required = false)]
     args: Vec<String>,
+    #[command(flatten)]
+    streams: StreamArgs,
+    #[arg(long, help = "Run the script made for the interpreter instead of the command, making it if outdated")]
+    via: Option<String>
   }
 }
 
-fn base_shortcut(name: &String, interpreters: &Option<Vec<String>>) -> Result<ShortcutBuilder> {
+#[derive(Debug, Args)]
+pub struct StreamArgs {
+  #[arg(long = "stdin-file", help = "Read the standard input from a file")]
+  stdin: Option<String>,
+  #[arg(long, help = "Write the standard output to a file")]
+  stdout: Option<String>,
+  #[arg(long, help = "Write the standard error to a file")]
+  stderr: Option<String>,
+  #[arg(long, default_value_t = false, help = "Append to the output files instead of truncating them")]
+  append: bool,
+  #[arg(long, default_value_t = false, help = "Discard the output not written to a file")]
+  quiet: bool
+}
+
+impl From<&StreamArgs> for Streams {
+  fn from(args: &StreamArgs) -> Self {
+    Streams {
+      stdin: args.stdin.as_ref().map(Into::into),
+      stdout: args.stdout.as_ref().map(Into::into),
+      stderr: args.stderr.as_ref().map(Into::into),
+      append: args.append,
+      quiet: args.quiet
+    }
+  }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BundleCommand {
+  #[clap(about = "Write the shortcut templates and their resources into a bundle")]
+  Export {
+    file: String,
+    #[arg(required = false)]
+    names: Vec<String>
+  },
+  #[clap(about = "Add the shortcut templates of a bundle")]
+  Import {
+    file: String,
+    #[arg(short, long)]
+    prefix: Option<String>,
+    #[arg(short, long, default_value_t = false)]
+    overwrite: bool,
+    #[arg(short, long, default_value_t = false)]
+    make: bool,
+    #[arg(long, default_value_t = false, help = "Import shortcuts shadowing existing commands")]
+    force: bool
+  }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SyncCommand {
+  #[clap(about = "Start tracking the data directory with git using the given remote")]
+  Init {
+    remote: String
+  },
+  #[clap(about = "Commit the local changes and send them to the remote")]
+  Push,
+  #[clap(about = "Receive the remote changes and make the updated shortcuts")]
+  Pull
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PathCommand {
+  #[clap(about = "Add the binaries directory to the PATH from the shell profile")]
+  Install,
+  #[clap(about = "Remove the binaries directory from the shell profile")]
+  Uninstall,
+  #[clap(about = "Show whether the binaries directory is in the PATH")]
+  Status
+}
+
+type StartupAction<'a> = dyn FnMut(&mut Controller, &mut ShortcutFile) -> Result<bool> + 'a;
+
+fn base_shortcut(
+  name: &String,
+  aliases: &[String],
+  interpreters: &Option<Vec<String>>,
+  description: &Option<String>,
+  tags: &[String]
+) -> Result<ShortcutBuilder> {
   Ok(
     Shortcut::builder()
     .name(name)
+    .aliases(Some(aliases.to_vec()))
+    .description(description.clone())
+    .tags(Some(tags.to_vec()))
     .interpreters(Interpreter::try_collect(interpreters.as_deref())?)
   )
 }
 
+fn store_shortcuts(controller: &mut Controller, mut shortcuts: Vec<Shortcut>, overwrite: bool, make: bool, force: bool) {
+  let action = |controller: &mut Controller, shortcut: &mut Shortcut| {
+    if controller.exists(&shortcut.name) && !overwrite {
+      output::log(format!("Skipped existing shortcut: {}", shortcut.name));
+      return Ok(false)
+    }
+    controller.save("import", shortcut.clone(), true, force, make).map(|_| true)
+  };
+  let count = output::operate_many(controller, &mut shortcuts, action);
+  output::notify_changes("Imported", count);
+}
+
+impl BundleCommand {
+  pub fn apply(&self, controller: &mut Controller) -> Result<()> {
+    match self {
+      Self::Export { file, names } => {
+        let shortcuts: Vec<Shortcut> = if names.is_empty() {
+          controller.get_all()?.filter_map(|(_, result)| result.ok()).map(|file| Shortcut::clone(&file)).collect()
+        } else {
+          controller.find_shortcuts(names)?.into_iter().map(|file| Shortcut::clone(&file)).collect()
+        };
+        let bundle = Bundle::new(shortcuts)?;
+        bundle.store(file)?;
+        output::notify_changes("Bundled", bundle.shortcuts.len() as i32);
+        Ok(())
+      },
+      Self::Import { file, prefix, overwrite, make, force } => {
+        let shortcuts = Bundle::load(file)?.localize(controller, prefix.as_deref())?;
+        store_shortcuts(controller, shortcuts, *overwrite, *make, *force);
+        Ok(())
+      }
+    }
+  }
+}
+
+impl SyncCommand {
+  pub fn apply(&self, controller: &mut Controller) -> Result<()> {
+    match self {
+      Self::Init { remote } => controller.sync().init(remote),
+      Self::Push => controller.sync_push(),
+      Self::Pull => {
+        let count = controller.sync_pull()?;
+        output::notify_changes("Updated", count);
+        Ok(())
+      }
+    }
+  }
+}
+
+impl PathCommand {
+  pub fn apply(&self, controller: &mut Controller) -> Result<()> {
+    let profile = ProfileFile::detect()?;
+    let file = paths::stringify_default(profile.path());
+    match self {
+      Self::Install => {
+        if profile.install(controller.bin_dir())? {
+          output::log(format!("Added the binaries directory to {}, restart the shell to apply it", file));
+        } else {
+          output::log(format!("Already installed in {}", file));
+        }
+      },
+      Self::Uninstall => {
+        if profile.uninstall()? {
+          output::log(format!("Removed the binaries directory from {}", file));
+        } else {
+          output::log(format!("Not installed in {}", file));
+        }
+      },
+      Self::Status => {
+        let active = paths::in_path(controller.bin_dir());
+        output::log(format!("PATH: {}", if active { "active" } else { "inactive" }));
+        output::log(format!("{}: {}", file, if profile.is_installed()? { "installed" } else { "not installed" }));
+      }
+    }
+    Ok(())
+  }
+}
+
 impl Command {
+  fn mutates(&self) -> bool {
+    match self {
+      Self::List { .. } | Self::History { .. } | Self::Stats { .. } | Self::Bin | Self::Doctor | Self::Path { .. } => false,
+      // The lock would be held while the shortcut runs, scripts made by --via are written atomically instead
+      Self::Run { .. } => false,
+      Self::Bundle { command } => matches!(command, BundleCommand::Import { .. }),
+      Self::Sync { command } => !matches!(command, SyncCommand::Push),
+      _ => true
+    }
+  }
+
   pub fn apply(&self, controller: &mut Controller) -> Result<()> {
+    let _lock = if self.mutates() { Some(controller.lock()?) } else { None };
+    self.run(controller)?;
+    if self.mutates() && controller.sync().is_initialized() {
+      controller.sync().commit()?;
+    }
+    Ok(())
+  }
+
+  fn run(&self, controller: &mut Controller) -> Result<()> {
     match self {
-      Self::Set { name, args, source, arg_offset, file, interpreters, make } => {
-        let shortcut = controller.new_shortcut_file(name, match source {
+      Self::Set { name, args, source, arg_offset, file, interpreters, aliases, description, tags, make, force, streams, timeout, retries, resources } => {
+        let resources = resources.iter().map(|source| controller.resource_from(name, source)).collect::<Result<Vec<_>>>()?;
+        if let Some((_, repeated)) = resources.iter().enumerate().find(|(i, x)| resources[..*i].iter().any(|y| y.name == x.name)) {
+          return Err(ScuError::name_error(&repeated.name, "is used by another resource of the shortcut"))
+        }
+        let shortcut = match source {
           Some(source) => {
-            let base = base_shortcut(name, interpreters)?;
-            let resource = controller.create_resource(source)?;
+            let base = base_shortcut(name, aliases, interpreters, description, tags)?;
+            let resource = controller.create_resource(name, source)?;
             let body = if *file { reader::from_file(source) } else { reader::from_stdin() }?;
             base.command_script(args.clone(), resource, body, *arg_offset)?
           },
-          None => base_shortcut(name, interpreters)?.command(args.clone()),
-        }.build());
-        shortcut.store()?;
-        if *make {
-          controller.make(&shortcut, None::<&[&str]>)?;
+          None => base_shortcut(name, aliases, interpreters, description, tags)?.command(args.clone()),
+        }.streams(Some(Streams::from(streams).absolute()?)).timeout(*timeout).retries(*retries)
+          .resources(Some(resources)).build();
+        controller.save("set", shortcut, false, *force, *make).map(|_| ())
+      },
+      Self::Edit { name, make } => controller.edit(name, *make).map(|_| ()),
+      Self::Import { from, source, interpreters, overwrite, make, force } => {
+        let imported = importer::import(from, source, &Interpreter::try_collect(interpreters.as_deref())?)?;
+        for rejected in &imported.rejected {
+          output::log(format!("Unable to import {} ({})", rejected.origin, rejected.reason));
         }
+        store_shortcuts(controller, imported.shortcuts, *overwrite, *make, *force);
         Ok(())
       },
+      Self::Bundle { command } => command.apply(controller),
+      Self::Sync { command } => command.apply(controller),
       Self::Unset { names, filename } =>
         controller.delete(names, *filename),
-      Self::Rename { name, new_name } => {
-        let mut shortcut = controller.find_shortcut(name)?;
-        controller.delete(&[name], false)?;
-        shortcut.name = new_name.clone();
-        shortcut.store()
-      }
-      Self::List { errors, verbose } =>
-        controller.list(*errors, *verbose),
-      Self::Make { names, interpreters, all, clean } => {
+      Self::Rename { name, new_name, force } =>
+        controller.rename(name, new_name, *force),
+      Self::List { errors, verbose, tags, grep, interpreter, startup, columns } => {
+        let filter = ShortcutFilter {
+          tags: tags.clone(),
+          grep: grep.clone(),
+          interpreter: interpreter.as_deref().map(Interpreter::try_from).transpose()?,
+          startup: *startup
+        };
+        output::list(&controller.list(&filter)?, *errors, *verbose, *columns);
+        Ok(())
+      },
+      Self::Make { names, interpreters, all, clean, force } => {
         let mut shortcuts = if *all {
           controller.get_all()?.filter_map(|(_, result)| result.ok()).collect()
         } else {
@@ -124,25 +436,104 @@ impl Command {
           controller.clean_dirs()?;
         }
         let action = |controller: &mut Controller, shortcut: &mut _|
-          controller.make(shortcut, interpreters.as_deref()).map(|_| true);
-        let count = controller.operate_many(&mut shortcuts, action);
-        controller.notify_changes("Made", count);
+          controller.make(shortcut, interpreters.as_deref(), *force).map(|_| true);
+        let count = output::operate_many(controller, &mut shortcuts, action);
+        output::notify_changes("Made", count);
         Ok(())
       },
+      Self::Export { shell, names, output } => {
+        let shortcuts: Vec<ShortcutFile> = if names.is_empty() {
+          controller.get_all()?.filter_map(|(_, result)| result.ok()).collect()
+        } else {
+          controller.find_shortcuts(names)?
+        };
+        let exported = controller.export(&shortcuts, shell)?;
+        match output {
+          Some(output) => fs::write(output, exported).map_err(|err| err.into()),
+          None => {
+            output::log(exported.trim_end());
+            Ok(())
+          }
+        }
+      },
       Self::Clean => controller.clean_dirs(),
-      Self::Bin => Ok(controller.log(paths::stringify_default(controller.bin_dir()))),
+      Self::Bin => {
+        output::log(paths::stringify_default(controller.bin_dir()));
+        Ok(())
+      },
+      Self::Path { command } => command.apply(controller),
       Self::Startup { names, quit, force } => {
         let mut shortcuts = controller.find_shortcuts(names)?;
-        let (action, verb) : (Box<dyn FnMut(&mut Controller, &mut ShortcutFile) -> Result<bool>>, _)= if !*quit {
+        let (action, verb): (Box<StartupAction>, _) = if !*quit {
           (Box::new(|controller, shortcut| controller.startup_set(shortcut, *force)), "Set")
         } else {
           (Box::new(|controller, shortcut| controller.startup_quit(shortcut)), "Quit")
         };
-        let count = controller.operate_many(&mut shortcuts, action);
-        controller.notify_changes(verb, count);
+        let count = output::operate_many(controller, &mut shortcuts, action);
+        output::notify_changes(verb, count);
         Ok(())
       }
-      Self::Run { name, args } => controller.execute(&controller.find_shortcut(name)?, args)
+      Self::History { name } => {
+        let journal = controller.history().entries()?;
+        for entry in journal.entries.iter().filter(|entry| name.as_ref().map(|x| entry.affects(x)).unwrap_or(true)) {
+          output::log(format!("#{} {} {} {}", entry.sequence, entry.elapsed(), entry.action, entry.names().join(", ")));
+        }
+        controller.warn_invalid_entries(journal.invalid);
+        Ok(())
+      },
+      Self::Stats { idle } => {
+        if !controller.config().log {
+          output::log("The execution log is disabled, enable it with `log = true` in config.toml");
+        }
+        let since = idle.map(|days| history::now().saturating_sub(days * 86400));
+        for stats in controller.stats()?.iter().filter(|stats| since.map(|since| stats.idle_since(since)).unwrap_or(true)) {
+          output::log(match stats.last_run {
+            None => format!("> {}: never run", stats.name),
+            Some(last_run) => format!(
+              "> {}: {} run{}, {:.0}% failed, last run {}{}",
+              stats.name, stats.runs, if stats.runs == 1 { "" } else { "s" }, stats.failure_rate() * 100.0, history::ago(last_run),
+              stats.last_failure.map(|last_failure| format!(", last failure {}", history::ago(last_failure))).unwrap_or_default()
+            )
+          });
+        }
+        Ok(())
+      },
+      Self::Undo => {
+        let entry = controller.undo()?;
+        output::log(format!("Undone #{} {} {}", entry.sequence, entry.action, entry.names().join(", ")));
+        Ok(())
+      },
+      Self::Restore { name, at } => controller.restore(name, *at),
+      Self::Migrate => {
+        let mut shortcuts = Vec::new();
+        for (_, shortcut) in controller.get_all()? {
+          match shortcut {
+            Ok(shortcut) => shortcuts.push(shortcut),
+            Err(err) => output::handle_error(err)
+          }
+        }
+        let count = output::operate_many(controller, &mut shortcuts, |controller, shortcut| controller.migrate(shortcut));
+        output::notify_changes("Migrated", count);
+        if count > 0 {
+          output::log(format!("Backups stored in {}", paths::stringify_default(controller.backup_dir())));
+        }
+        Ok(())
+      },
+      Self::Doctor => {
+        let checks = doctor::diagnose(controller)?;
+        checks.iter().for_each(output::log);
+        let count = |status: Status| checks.iter().filter(|check| check.status == status).count();
+        let failed = count(Status::Fail);
+        output::log(format!("{} passed, {} warnings, {} failed", count(Status::Pass), count(Status::Warn), failed));
+        if failed > 0 {
+          return Err(ScuError::ChecksFailed { count: failed })
+        }
+        Ok(())
+      },
+      Self::Run { name, args, streams, via } => {
+        let via = via.as_deref().map(Interpreter::try_from).transpose()?;
+        controller.execute(&controller.find_shortcut(name)?, args, &streams.into(), via.as_ref())
+      }
     }
   }
 }
\ No newline at end of file
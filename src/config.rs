@@ -0,0 +1,23 @@
+use serde::{Serialize, Deserialize};
+use std::{fs, path};
+
+use crate::{errors::Result, store::StoreKind};
+
+/// Settings read from `config.toml` in the data directory, every field is optional
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+  pub store: StoreKind,
+  /// Whether `scu run` appends each execution to the log
+  pub log: bool
+}
+
+impl Config {
+  /// Defaults are used when the file does not exist
+  pub fn load(path: impl AsRef<path::Path>) -> Result<Self> {
+    if !path.as_ref().exists() {
+      return Ok(Config::default())
+    }
+    toml::from_str(fs::read_to_string(path)?.as_str()).map_err(|err| err.into())
+  }
+}
@@ -1,119 +1,528 @@
-use std::{fs, path, env, fmt, borrow::Borrow, process::Command};
+use std::{fs, path, env, fmt, rc::Rc, cell::RefCell, collections::{BTreeMap, BTreeSet}, process::Command, thread, time::Instant};
 
 use crate::{
-  shortcut::{Shortcut, ShortcutFile},
+  shortcut::{Shortcut, ShortcutBody, ShortcutFile, ShortcutFilter},
   errors::{Result, ScuError},
+  paths,
+  suggestions,
   interpreter::Interpreter,
+  script::Shell,
+  streams::Streams,
+  resource::{Resource, ResourceSource},
+  reader,
+  execution::{Execution, Outcome},
+  lock::Lock,
+  migration,
+  config::Config,
+  store::{ShortcutStore, StoreKind, DirectoryStore, SingleFileStore, SUFFIX},
+  sync::Sync,
+  history::{self, History, Change},
+  log::{Log, Run, Stats},
   startup::{StartupReference}
 };
 
+/// Owner of the `scu_data` directory, every change to the templates and the generated scripts goes through it
 pub struct Controller {
-  path: path::PathBuf
+  path: path::PathBuf,
+  store: Rc<dyn ShortcutStore>,
+  config: Config,
+  warnings: RefCell<Vec<ScuError>>
+}
+
+/// Result of [`Controller::list`]
+#[derive(Debug)]
+pub struct Listing {
+  pub shortcuts: Vec<ShortcutFile>,
+  /// Key of the templates that could not be loaded with the reason
+  pub invalid: Vec<(String, ScuError)>
 }
 
 const BASE_DIR: &str = "scu_data";
 const META_DIR: &str = "meta";
 const BIN_DIR: &str = "bin";
-const RES_DIR: &str = "res";
-const SUFFIX: &str = ".toml";
+pub(crate) const RES_DIR: &str = "res";
+const HISTORY_DIR: &str = "history";
+const BACKUP_DIR: &str = "backups";
+const LOCK_FILE: &str = ".lock";
+const LOG_FILE: &str = "log";
+const CONFIG_FILE: &str = "config.toml";
+const SINGLE_FILE: &str = "shortcuts.toml";
 
 impl Controller {
+  /// Controller for the `scu_data` directory next to the running executable, using the configured store
   pub fn new() -> Result<Self> {
-    Ok(Controller { path: env::current_exe()?.parent().unwrap().into() })
+    Self::from_root(env::current_exe()?.parent().unwrap())
+  }
+
+  /// Controller for the `scu_data` directory inside the given root, using the configured store
+  pub fn from_root(root: impl AsRef<path::Path>) -> Result<Self> {
+    let path = root.as_ref().to_path_buf();
+    let data_dir = path.join(BASE_DIR);
+    let config = Config::load(data_dir.join(CONFIG_FILE))?;
+    let store: Rc<dyn ShortcutStore> = match config.store {
+      StoreKind::Directory => Rc::new(DirectoryStore::new(data_dir.join(META_DIR))),
+      StoreKind::SingleFile => Rc::new(SingleFileStore::new(data_dir.join(SINGLE_FILE)))
+    };
+    Ok(Controller { path, store, config, warnings: RefCell::new(Vec::new()) })
   }
 
+  /// Replaces the store the templates are kept in
+  pub fn with_store(mut self, store: impl ShortcutStore + 'static) -> Self {
+    self.store = Rc::new(store);
+    self
+  }
+
+  /// Root of the data directory, containing the templates, the scripts, the resources and the history
+  pub fn data_dir(&self) -> path::PathBuf {
+    self.path.as_path().join(BASE_DIR)
+  }
+
+  /// Directory of the stored templates
   pub fn meta_dir(&self) -> path::PathBuf {
     self.path.as_path().join(BASE_DIR).join(META_DIR)
   }
 
+  /// Directory of the generated scripts, it must be in the PATH for the shortcuts to work
   pub fn bin_dir(&self) -> path::PathBuf {
     self.path.as_path().join(BASE_DIR).join(BIN_DIR)
   }
 
+  /// Directory of the resources used by script-backed shortcuts
   pub fn res_dir(&self) -> path::PathBuf {
     self.path.as_path().join(BASE_DIR).join(RES_DIR)
   }
 
-  pub fn create_resource(&self, file: impl AsRef<path::Path>) -> Result<path::PathBuf> {
-    Ok(self.res_dir().join(file.as_ref().file_name().ok_or(ScuError::StringError("Unable to create resource".into()))?))
+  /// Directory of the history entries
+  pub fn history_dir(&self) -> path::PathBuf {
+    self.path.as_path().join(BASE_DIR).join(HISTORY_DIR)
+  }
+
+  /// Directory where migrate keeps the original template files
+  pub fn backup_dir(&self) -> path::PathBuf {
+    self.path.as_path().join(BASE_DIR).join(BACKUP_DIR)
+  }
+
+  /// Journal of the changes made to the templates
+  pub fn history(&self) -> History {
+    History::new(self.history_dir())
+  }
+
+  /// Settings read from the configuration file when the controller was created
+  pub fn config(&self) -> &Config {
+    &self.config
+  }
+
+  /// Log of the shortcuts run, only written when enabled in the configuration
+  pub fn log(&self) -> Log {
+    Log::new(self.data_dir().join(LOG_FILE))
+  }
+
+  /// Usage of every valid template according to the log, the most used first
+  pub fn stats(&self) -> Result<Vec<Stats>> {
+    let shortcuts: Vec<ShortcutFile> = self.get_all()?.filter_map(|(_, shortcut)| shortcut.ok()).collect();
+    self.log().stats(shortcuts.iter().map(|shortcut| shortcut.name.as_str()))
+  }
+
+  /// Git synchronisation of the templates and resources
+  pub fn sync(&self) -> Sync {
+    Sync::new(self.data_dir())
+  }
+
+  /// Pushes the templates, refused while a resource copies a directory the other machines do not have
+  pub fn sync_push(&self) -> Result<()> {
+    for (_, shortcut) in self.get_all()? {
+      let shortcut = match shortcut {
+        Ok(shortcut) => shortcut,
+        Err(_) => continue
+      };
+      if let Some(resource) = shortcut.resources.iter().flatten().find(|resource| matches!(resource.source, ResourceSource::Directory(_))) {
+        return Err(ScuError::NotPortable { name: shortcut.name.clone(), resource: resource.name.clone() })
+      }
+    }
+    self.sync().push()
+  }
+
+  /// Pulls the remote templates and makes the ones that changed, returns how many changed
+  pub fn sync_pull(&mut self) -> Result<i32> {
+    let before: BTreeMap<String, Shortcut> = self.get_all()?
+      .filter_map(|(key, result)| result.ok().map(|file| (key, Shortcut::clone(&file))))
+      .collect();
+    self.sync().pull()?;
+    let keys: BTreeSet<String> = before.keys().cloned().chain(self.store.list()?).collect();
+    let mut count = 0;
+    for key in keys {
+      let previous = before.get(&key);
+      let current = self.load(&key).ok();
+      let stored = |shortcut: Option<&Shortcut>| shortcut.and_then(|shortcut| migration::to_string(shortcut).ok());
+      if stored(previous) == stored(current.as_deref()) {
+        continue
+      }
+      if let Some(previous) = previous {
+        self.remove_scripts(previous)?;
+      }
+      if let Some(current) = &current {
+        self.make(current, None::<&[&str]>, true)?;
+      }
+      count += 1;
+    }
+    Ok(count)
+  }
+
+  /// Location in the resources directory of the shortcut for a file to be used as resource
+  pub fn create_resource(&self, shortcut: impl AsRef<str>, file: impl AsRef<path::Path>) -> Result<path::PathBuf> {
+    let file_name = file.as_ref().file_name().ok_or(ScuError::ResourceError { path: file.as_ref().to_path_buf(), source: None })?;
+    Ok(self.resource_path(shortcut, file_name.to_string_lossy()))
+  }
+
+  /// Points the resources to the resources directory of the shortcut, for templates renamed or brought from elsewhere
+  pub fn localize_resources(&self, shortcut: &mut Shortcut) -> Result<()> {
+    Self::check_resource_names(shortcut)?;
+    if let ShortcutBody::CommandWithScript { script, .. } = &mut shortcut.body {
+      let source = script.to_string_lossy().to_string();
+      let file_name = source.rsplit(['/', '\\']).next().unwrap_or_default();
+      if let Some(reason) = paths::invalid_name_reason(file_name) {
+        return Err(ScuError::name_error(file_name, format!("script of {}: {}", shortcut.name, reason)))
+      }
+      *script = self.create_resource(&shortcut.name, file_name)?;
+    }
+    for resource in shortcut.resources.iter_mut().flatten() {
+      resource.path = self.resource_path(&shortcut.name, &resource.name);
+    }
+    Ok(())
+  }
+
+  /// Location of a resource in the resources directory of the shortcut
+  pub fn resource_path(&self, shortcut: impl AsRef<str>, name: impl AsRef<str>) -> path::PathBuf {
+    self.res_dir().join(shortcut.as_ref()).join(name.as_ref())
   }
 
+  /// Resource of the shortcut named after the source, files are embedded with their mode and directories are copied
+  pub fn resource_from(&self, shortcut: impl AsRef<str>, source: impl AsRef<path::Path>) -> Result<Resource> {
+    let source = source.as_ref();
+    let error = |err| ScuError::ResourceError { path: source.to_path_buf(), source: err };
+    let name = source.file_name().ok_or(error(None))?.to_string_lossy().to_string();
+    let (source, mode) = if source.is_dir() {
+      (ResourceSource::Directory(path::absolute(source).map_err(|err| error(Some(err)))?), None)
+    } else {
+      (ResourceSource::Content(reader::from_file(source)?), paths::mode(source))
+    };
+    Ok(Resource { path: self.resource_path(shortcut, &name), name, source, mode })
+  }
+
+  /// Exclusive lock over the data directory, held until the returned value is dropped
+  pub fn lock(&self) -> Result<Lock> {
+    Lock::acquire(self.data_dir().join(LOCK_FILE))
+  }
+
+  /// Creates the data directory structure if it is missing
   pub fn setup(&mut self) -> Result<()> {
-    fs::create_dir_all(self.meta_dir())?;
+    self.store.setup()?;
     fs::create_dir_all(self.bin_dir())?;
+    fs::create_dir_all(self.history_dir())?;
     fs::create_dir_all(self.res_dir()).map_err(|err| err.into())
   }
 
+  /// Loads a template with its resources located in this data directory, templates pulled by sync or copied
+  /// from another installation keep the paths of the directory they were written in
+  fn load(&self, key: impl AsRef<str>) -> Result<ShortcutFile> {
+    let mut file = ShortcutFile::load(self.store.clone(), key)?;
+    self.localize_resources(&mut file)?;
+    Ok(file)
+  }
+
+  /// Whether a template is stored for the name, aliases are not considered
+  pub fn exists(&self, name: impl AsRef<str>) -> bool {
+    self.store.contains(name.as_ref())
+  }
+
+  /// Binds a template to its key in the store, it is not stored until [`ShortcutFile::store`]
   pub fn new_shortcut_file(&mut self, name: impl AsRef<str>, file: Shortcut) -> ShortcutFile {
-    ShortcutFile::new(file, self.meta_dir().join(format!("{}{}", name.as_ref(), SUFFIX)))
+    ShortcutFile::new(file, name, self.store.clone())
   }
 
-  pub fn delete(&mut self, names: &[impl AsRef<str>], by_filename: bool) -> Result<()> {
-    let targets: Vec<&str> = names.into_iter().map(|x| x.as_ref()).collect();
-    let filter: Box<dyn Fn(&fs::DirEntry)->bool> = if by_filename {
-      Box::new(
-        |entry| targets.contains(&entry.file_name().to_str().unwrap())
-      )
+  /// Stores the template after checking its names, records the change in the history and makes the scripts when
+  /// asked. An existing template is replaced when `replace` is set, otherwise only when forced
+  pub fn save(&mut self, action: impl AsRef<str>, shortcut: Shortcut, replace: bool, force: bool, make: bool) -> Result<ShortcutFile> {
+    if !replace && self.exists(&shortcut.name) {
+      self.warn_or_refuse(&shortcut.name, "already exists", force)?;
+    }
+    self.check_names(&shortcut, force)?;
+    self.check_collisions(&shortcut, &shortcut.name)?;
+    let change = self.snapshot(&shortcut.name);
+    let file = self.new_shortcut_file(shortcut.name.clone(), shortcut);
+    file.store()?;
+    self.record(action, vec![change])?;
+    if make {
+      self.make(&file, None::<&[&str]>, true)?;
+    }
+    Ok(file)
+  }
+
+  /// Opens the template and then its script body in the user editor and saves them, the template changes are
+  /// kept even when editing the body is cancelled
+  pub fn edit(&mut self, name: impl AsRef<str>, make: bool) -> Result<ShortcutFile> {
+    let mut shortcut = self.find_shortcut(name)?;
+    let replaces = shortcut.name.clone();
+    shortcut.edit_template(|content| self.check_invalid_names(content).and_then(|_| self.check_collisions(content, &replaces)))?;
+    let edited_body = shortcut.edit_body();
+    let file = self.save("edit", Shortcut::clone(&shortcut), true, true, make && edited_body.is_ok())?;
+    edited_body.map(|_| file)
+  }
+
+  /// Removes the templates and their scripts by name, alias or key, the missing ones are reported as warnings
+  pub fn delete(&mut self, names: &[impl AsRef<str>], by_key: bool) -> Result<()> {
+    let targets: Vec<&str> = names.iter().map(|x| x.as_ref())
+      .map(|x| if by_key { x.strip_suffix(SUFFIX).unwrap_or(x) } else { x })
+      .collect();
+    let entries: Vec<(String, Option<ShortcutFile>)> = self.store.list()?.into_iter()
+      .map(|key| { let file = self.load(&key).ok(); (key, file) })
+      .collect();
+    let matches = |target: &str, (key, file): &(String, Option<ShortcutFile>)| if by_key {
+      key == target
     } else {
-      Box::new(
-        |entry| ShortcutFile::load(entry.path()).map(|file| targets.contains(&file.name.as_str())).unwrap_or(false)
-      )
+      file.as_ref().map(|file| file.is_named(target)).unwrap_or(false)
     };
-    for entry in fs::read_dir(self.meta_dir())?.into_iter().filter_map(|x| x.ok()).filter(filter) {
-      if entry.metadata().map(|m| m.is_file()).unwrap_or(true) {
-        fs::remove_file(entry.path())?;
-      } else {
-        fs::remove_dir(entry.path())?;
+    let (found, missing): (Vec<&str>, Vec<&str>) = targets.iter()
+      .partition(|target| entries.iter().any(|entry| matches(target, entry)));
+    match missing.first() {
+      Some(target) if found.is_empty() => return Err(self.not_found(target)),
+      _ => self.warnings.borrow_mut().extend(missing.iter().map(|target| self.not_found(target)))
+    }
+    let mut changes = Vec::new();
+    for entry in entries.iter().filter(|entry| targets.iter().any(|target| matches(target, entry))) {
+      if let (_, Some(shortcut)) = entry {
+        self.remove_scripts(shortcut)?;
+        changes.push(self.snapshot(&shortcut.name));
+      }
+      self.store.delete(&entry.0)?;
+    }
+    self.record("unset", changes)
+  }
+
+  /// Renames a template and moves its generated scripts to the new name
+  pub fn rename(&mut self, name: impl AsRef<str>, new_name: impl AsRef<str>, force: bool) -> Result<()> {
+    let shortcut = self.find_shortcut(name)?;
+    let mut renamed = self.new_shortcut_file(&new_name, Shortcut::clone(&shortcut));
+    renamed.name = new_name.as_ref().to_string();
+    self.localize_resources(&mut renamed)?;
+    self.check_names(&renamed, force)?;
+    self.check_collisions(&renamed, &shortcut.name)?;
+    let changes = vec![self.snapshot(&shortcut.name), self.snapshot(&renamed.name)];
+    let interpreters: Vec<&str> = self.remove_scripts(&shortcut)?.iter().map(|x| x.name()).collect();
+    let (resources, renamed_resources) = (self.res_dir().join(&shortcut.name), self.res_dir().join(&renamed.name));
+    if resources.is_dir() && !renamed_resources.exists() {
+      fs::rename(resources, renamed_resources)?;
+    }
+    self.store.rename(shortcut.key(), renamed.key())?;
+    renamed.store()?;
+    if !interpreters.is_empty() {
+      self.make(&renamed, Some(&interpreters), true)?;
+    }
+    self.record("rename", changes)
+  }
+
+  /// Current state of a template, to be recorded in the history before changing it
+  pub fn snapshot(&self, name: impl AsRef<str>) -> Change {
+    Change {
+      name: name.as_ref().to_string(),
+      previous: self.load(&name).ok().map(|file| Shortcut::clone(&file))
+    }
+  }
+
+  /// Adds an entry to the history, empty change lists are not recorded
+  pub fn record(&self, action: impl AsRef<str>, changes: Vec<Change>) -> Result<()> {
+    if changes.is_empty() {
+      return Ok(())
+    }
+    self.history().record(action, changes)
+  }
+
+  fn restore_change(&mut self, change: &Change) -> Result<()> {
+    let current = self.load(&change.name).ok();
+    if let Some(current) = &current {
+      self.remove_scripts(current)?;
+      if let Some(startup) = &current.startup {
+        if change.previous.as_ref().and_then(|x| x.startup.as_ref()).is_none() {
+          startup.delete()?;
+        }
+      }
+    }
+    match &change.previous {
+      Some(previous) => {
+        let mut restored = self.new_shortcut_file(&change.name, previous.clone());
+        if restored.startup.is_some() && current.as_ref().and_then(|x| x.startup.as_ref()).is_none() {
+          let startup = StartupReference::create(&restored)?;
+          restored.update_startup_reference(Some(startup));
+        }
+        restored.store()?;
+        self.make(&restored, None::<&[&str]>, true)
+      },
+      None => match current {
+        Some(current) => current.remove(),
+        None => Ok(())
+      }
+    }
+  }
+
+  /// Reports the history entries that could not be read as warnings
+  pub fn warn_invalid_entries(&self, invalid: Vec<(String, ScuError)>) {
+    self.warnings.borrow_mut().extend(invalid.into_iter().map(|(file_name, err)| ScuError::InvalidHistoryEntry {
+      path: self.history_dir().join(file_name),
+      reason: Box::new(err)
+    }));
+  }
+
+  /// Reverts the last entry of the history and returns it
+  pub fn undo(&mut self) -> Result<history::Entry> {
+    let history = self.history();
+    let mut journal = history.entries()?;
+    self.warn_invalid_entries(journal.invalid);
+    let entry = journal.entries.pop().ok_or(ScuError::NothingToUndo)?;
+    for change in entry.changes.iter().rev() {
+      self.restore_change(change)?;
+    }
+    history.remove(entry.sequence)?;
+    Ok(entry)
+  }
+
+  /// Brings a template back to its state before the given history entry
+  pub fn restore(&mut self, name: impl AsRef<str>, sequence: u64) -> Result<()> {
+    let entry = self.history().get(sequence)?;
+    let change = entry.changes.iter().find(|change| change.name == name.as_ref()).ok_or(
+      ScuError::HistoryEntryMismatch { sequence, name: name.as_ref().to_string() }
+    )?;
+    let snapshot = self.snapshot(&name);
+    self.restore_change(change)?;
+    self.record("restore", vec![snapshot])
+  }
+
+  /// Fails if any name of the shortcut is already used by another template than `replaces`
+  pub fn check_collisions(&self, shortcut: &Shortcut, replaces: impl AsRef<str>) -> Result<()> {
+    let names: Vec<&String> = shortcut.names().collect();
+    if let Some((_, name)) = names.iter().enumerate().find(|(i, name)| names[..*i].contains(name)) {
+      return Err(ScuError::name_error(name, format!("used more than once by shortcut {}", shortcut.name)))
+    }
+    for other in self.get_all()?.filter_map(|(_, result)| result.ok()) {
+      if other.name == replaces.as_ref() {
+        continue
+      }
+      if let Some(name) = names.iter().find(|name| other.is_named(name)) {
+        return Err(ScuError::name_error(name, format!("already used by shortcut {}", other.name)))
       }
     }
     Ok(())
   }
 
-  pub fn get_all(&self) -> Result<impl Iterator<Item = (fs::DirEntry, Result<ShortcutFile>)>> {
-    Ok(fs::read_dir(self.meta_dir())?.into_iter().filter_map(|x| x.ok()).map(|entry| {
-      let path = entry.path();
-      (entry, ShortcutFile::load(path))
-    }))
+  /// Fails if a name is invalid, shadowing an executable from the PATH only warns when forced
+  pub fn check_names(&self, shortcut: &Shortcut, force: bool) -> Result<()> {
+    self.check_invalid_names(shortcut)?;
+    for name in shortcut.names() {
+      if let Some(executable) = paths::find_executable(name, Some(&self.bin_dir())) {
+        self.warn_or_refuse(name, format!("shadows {}", executable.display()), force)?;
+      }
+    }
+    Ok(())
   }
 
-  pub fn list(&self, notify_errors: bool, verbose: bool) -> Result<()> {
-    Ok(for (entry, shortcut) in self.get_all()? {
-      match shortcut {
-        Ok(shortcut) => {
-          println!("> {} => {}", shortcut.name, shortcut.body);
-          if verbose {
-            if let Some(interpreters) = &shortcut.interpreters {
-              println!(
-                " |> Interpreters: {}",
-                interpreters.iter().map(|i| i.name().to_string())
-                  .reduce(|a, b| format!("{}, {}", a, b)).unwrap_or(String::new())
-              )
-            }
-            if let Some(startup) = &shortcut.startup {
-              println!(" |> Startup: {}", startup);
-            }
-          }
-        },
-        Err(err) if notify_errors => {
-          println!("> Invalid file: {}", entry.file_name().to_str().unwrap());
-          if verbose {
-            println!("'''\n{}'''", err);
+  /// Fails on the names that can not be used as file names, unlike shadowing it can not be forced
+  pub fn check_invalid_names(&self, shortcut: &Shortcut) -> Result<()> {
+    for name in shortcut.names() {
+      if let Some(reason) = paths::invalid_name_reason(name) {
+        return Err(ScuError::name_error(name, reason))
+      }
+    }
+    Self::check_resource_names(shortcut)
+  }
+
+  /// Resources are written in the resources directory of the shortcut, their names must be plain file names
+  fn check_resource_names(shortcut: &Shortcut) -> Result<()> {
+    for resource in shortcut.resources.iter().flatten() {
+      if let Some(reason) = paths::invalid_name_reason(&resource.name) {
+        return Err(ScuError::name_error(&resource.name, format!("resource of {}: {}", shortcut.name, reason)))
+      }
+      if let ResourceSource::Files(files) = &resource.source {
+        for file in files.keys() {
+          if let Some(reason) = Resource::invalid_file_reason(file) {
+            return Err(ScuError::name_error(file, format!("file of resource {} of {}: {}", resource.name, shortcut.name, reason)))
           }
         }
-        _ => {}
       }
-    })
+    }
+    Ok(())
+  }
+
+  /// Fails with the reason unless forced, then it is kept as a warning
+  pub fn warn_or_refuse(&self, name: impl AsRef<str>, reason: impl fmt::Display, force: bool) -> Result<()> {
+    if force {
+      self.warnings.borrow_mut().push(ScuError::name_error(name, reason));
+      Ok(())
+    } else {
+      Err(ScuError::name_error(name, format!("{}, use --force to proceed anyway", reason)))
+    }
+  }
+
+  /// Key of every stored template with the result of loading it
+  pub fn get_all(&self) -> Result<impl Iterator<Item = (String, Result<ShortcutFile>)> + '_> {
+    Ok(self.store.list()?.into_iter().map(|key| {
+      let file = self.load(&key);
+      (key, file)
+    }))
+  }
+
+  /// Rewrites a template stored in an older schema version, the original file is kept in the backups directory
+  pub fn migrate(&self, shortcut: &ShortcutFile) -> Result<bool> {
+    if shortcut.version() >= migration::CURRENT_VERSION {
+      return Ok(false)
+    }
+    self.store.backup(shortcut.key(), shortcut.version(), &self.backup_dir())?;
+    shortcut.store().map(|_| true)
+  }
+
+  /// Templates matching the filter sorted by name, along with the files that could not be loaded
+  pub fn list(&self, filter: &ShortcutFilter) -> Result<Listing> {
+    let mut listing = Listing { shortcuts: Vec::new(), invalid: Vec::new() };
+    for (key, shortcut) in self.get_all()? {
+      match shortcut {
+        Ok(shortcut) if filter.matches(&shortcut) => listing.shortcuts.push(shortcut),
+        Ok(_) => {},
+        Err(err) => listing.invalid.push((key, err))
+      }
+    }
+    listing.shortcuts.sort_by(|a, b| a.name.cmp(&b.name));
+    listing.invalid.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(listing)
   }
 
+  /// Template by name or alias, the error suggests close names
   pub fn find_shortcut(&self, name: impl AsRef<str>) -> Result<ShortcutFile> {
-    ShortcutFile::load(self.meta_dir().join(format!("{}{}", name.as_ref(), SUFFIX)))
+    if !self.exists(&name) {
+      return match self.get_all()?.filter_map(|(_, result)| result.ok()).find(|x| x.is_named(&name)) {
+        Some(shortcut) => Ok(shortcut),
+        None => Err(self.not_found(name))
+      }
+    }
+    self.load(name)
   }
 
+  /// Not found error with the closest names and aliases as suggestions
+  pub fn not_found(&self, name: impl AsRef<str>) -> ScuError {
+    let shortcuts: Vec<ShortcutFile> = self.get_all().map(
+      |all| all.filter_map(|(_, result)| result.ok()).collect()
+    ).unwrap_or_default();
+    ScuError::ShortcutNotFound {
+      name: name.as_ref().to_string(),
+      suggestions: suggestions::closest(&name, shortcuts.iter().flat_map(|x| x.names()).map(|x| x.as_str()))
+    }
+  }
+
+  /// Templates by name or alias, fails on the first missing
   pub fn find_shortcuts(&self, names: &[impl AsRef<str>]) -> Result<Vec<ShortcutFile>> {
     names.iter().map(|name| self.find_shortcut(name)).collect::<Result<_>>()
   }
 
-  pub fn make(&mut self, shortcut: &ShortcutFile, interpreters: Option<&[impl AsRef<str>]>) -> Result<()> {
+  /// Writes the scripts of the shortcut, for the given interpreters or else the ones of the template or else all
+  pub fn make(&mut self, shortcut: &ShortcutFile, interpreters: Option<&[impl AsRef<str>]>, force: bool) -> Result<()> {
+    self.check_names(shortcut, force)?;
     let collected_interpreters = Interpreter::try_collect(interpreters)?;
     let all_interpreters = Interpreter::all();
     let interpreters = [
@@ -122,31 +531,101 @@ impl Controller {
       Some(all_interpreters.as_slice())
     ].into_iter().find(|x| x.is_some()).unwrap().unwrap();
     shortcut.write_resources()?;
-    Ok(for interpreter in interpreters {
-      let script = shortcut.script(interpreter)?;
-      let path = self.bin_dir().join(format!("{}{}", shortcut.name, interpreter.preferred_extension()));
-      fs::write(path, format!("{}", script))?;
-    })
+    for interpreter in interpreters {
+      if !shortcut.execution().is_empty() && !interpreter.applies_limits() {
+        self.warnings.borrow_mut().push(ScuError::LimitsIgnored { name: shortcut.name.clone(), interpreter: interpreter.clone() });
+      }
+      let path = self.make_script(shortcut, interpreter)?;
+      for alias in shortcut.aliases.iter().flatten() {
+        self.write_alias(&path, self.script_path(alias, interpreter))?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Writes the script of the shortcut for the interpreter unless it is up to date, returns its location.
+  /// The resources are expected to be written already
+  pub fn make_script(&self, shortcut: &Shortcut, interpreter: &Interpreter) -> Result<path::PathBuf> {
+    let script = shortcut.script(interpreter)?.to_string();
+    let path = self.script_path(&shortcut.name, interpreter);
+    if fs::read_to_string(&path).ok().as_ref() != Some(&script) {
+      paths::write_atomic(&path, script, true)?;
+    }
+    Ok(path)
+  }
+
+  /// Location of the script generated for the name and interpreter
+  pub fn script_path(&self, name: impl AsRef<str>, interpreter: &Interpreter) -> path::PathBuf {
+    self.bin_dir().join(format!("{}{}", name.as_ref(), interpreter.preferred_extension()))
+  }
+
+  #[cfg(unix)]
+  fn write_alias(&self, script: &path::Path, alias: path::PathBuf) -> Result<()> {
+    if alias.symlink_metadata().is_ok() {
+      fs::remove_file(&alias)?;
+    }
+    std::os::unix::fs::symlink(script.file_name().unwrap(), alias).map_err(|err| err.into())
+  }
+
+  #[cfg(not(unix))]
+  fn write_alias(&self, script: &path::Path, alias: path::PathBuf) -> Result<()> {
+    paths::write_atomic(alias, fs::read(script)?, true).map_err(|err| err.into())
+  }
+
+  /// Removes the generated scripts of every name of the shortcut, returns the interpreters they were made for
+  pub fn remove_scripts(&self, shortcut: &Shortcut) -> Result<Vec<Interpreter>> {
+    let mut removed = Vec::new();
+    for interpreter in Interpreter::all().into_iter().chain([Interpreter::Batch]) {
+      for name in shortcut.names() {
+        let path = self.script_path(name, &interpreter);
+        if path.symlink_metadata().is_ok() {
+          fs::remove_file(path)?;
+          if !removed.contains(&interpreter) {
+            removed.push(interpreter.clone());
+          }
+        }
+      }
+    }
+    Ok(removed)
+  }
+
+  /// Shell definitions for every name of the shortcuts, to be sourced instead of using the scripts
+  pub fn export(&self, shortcuts: &[ShortcutFile], shell: &Shell) -> Result<String> {
+    let mut definitions = Vec::new();
+    for shortcut in shortcuts {
+      shortcut.write_resources()?;
+      for name in shortcut.names() {
+        definitions.push(format!("{}\n", shortcut.definition(shell, name)?));
+      }
+    }
+    Ok(definitions.join("\n"))
   }
 
+  /// Registers the shortcut to run at startup, returns whether it changed
   pub fn startup_set(&mut self, shortcut: &mut ShortcutFile, force: bool) -> Result<bool> {
     if shortcut.startup.is_none() || force {
+      let change = self.snapshot(&shortcut.name);
       let startup = StartupReference::create(shortcut)?;
       shortcut.update_startup_reference(Some(startup));
-      return shortcut.store().map(|_| true)
+      shortcut.store()?;
+      return self.record("startup", vec![change]).map(|_| true)
     }
     Ok(false)
   }
   
+  /// Removes the shortcut from startup, returns whether it changed
   pub fn startup_quit(&mut self, shortcut: &mut ShortcutFile) -> Result<bool> {
     if let Some(startup) = &shortcut.startup {
+      let change = self.snapshot(&shortcut.name);
       startup.delete()?;
       shortcut.update_startup_reference(None);
-      return shortcut.store().map(|_| true)
+      shortcut.store()?;
+      return self.record("startup", vec![change]).map(|_| true)
     }
     Ok(false)
   }
 
+  /// Removes every generated script and resource
   pub fn clean_dirs(&mut self) -> Result<()> {
     fs::remove_dir_all(self.bin_dir())?;
     fs::create_dir(self.bin_dir())?;
@@ -154,34 +633,62 @@ impl Controller {
     fs::create_dir(self.res_dir()).map_err(|err| err.into())
   }
 
-  pub fn execute(&self, shortcut: &ShortcutFile, args: &[String]) -> Result<()> {
-    let shortcut_command = shortcut.command();
-    Command::new(shortcut_command.first().unwrap()).args(&shortcut_command[1..]).args(args).spawn()?;
-    Ok(())
-  }
-
-  pub fn notify_changes(&self, verb: impl fmt::Display, count: i32) {
-    println!("{} {} shortcut{}", verb, count, if count == 1 { "" } else { "s" })
-  }
-
-  pub fn handle_error(&self, err: impl Borrow<ScuError>) {
-    println!("Got error: {}", err.borrow())
-  }
-
-  pub fn handle_result<T>(&self, result: impl Borrow<Result<T>>) {
-    if let Err(err) = result.borrow() {
-      self.handle_error(err);
+  /// Runs the command of the shortcut with the extra arguments until it succeeds or runs out of attempts,
+  /// `streams` take precedence over the ones of the shortcut and the failed attempts that are retried are kept as warnings.
+  ///
+  /// When an interpreter is given its script is made if outdated and run by the interpreter instead of the command,
+  /// the redirections, timeout and retries of the shortcut are left to the script like when it is run from the PATH.
+  /// The run is appended to the log when it is enabled
+  pub fn execute(&self, shortcut: &ShortcutFile, args: &[String], streams: &Streams, via: Option<&Interpreter>) -> Result<()> {
+    let (timestamp, start) = (history::now(), Instant::now());
+    let result = match via {
+      Some(interpreter) => shortcut.write_resources().and_then(|_| self.make_script(shortcut, interpreter)).and_then(
+        |script| self.attempt_all(&shortcut.name, interpreter.invocation(script), args, streams, &Execution::default())
+      ),
+      None => self.attempt_all(
+        &shortcut.name, shortcut.command(), args, &streams.or(&shortcut.streams.clone().unwrap_or_default()), &shortcut.execution()
+      )
+    };
+    if self.config.log {
+      self.log().append(&Run {
+        timestamp,
+        name: shortcut.name.clone(),
+        args: args.to_vec(),
+        duration: start.elapsed().as_millis() as u64,
+        code: match &result {
+          Ok(()) => Some(0),
+          Err(ScuError::ExecutionFailed { outcome: Outcome::Failed(code), .. }) => *code,
+          Err(_) => None
+        }
+      })?;
     }
+    result
   }
 
-  pub fn log(&self, data: impl fmt::Display) {
-    println!("{}", data)
+  fn attempt_all(&self, name: &str, command: Vec<String>, args: &[String], streams: &Streams, execution: &Execution) -> Result<()> {
+    let binary = command.first().ok_or(ScuError::EmptyCommand)?;
+    let attempts = execution.attempts();
+    for attempt in 1..=attempts {
+      thread::sleep(Execution::backoff(attempt));
+      let mut process = Command::new(binary);
+      process.args(&command[1..]).args(args);
+      streams.apply(&mut process)?;
+      let outcome = execution.attempt(&mut process)?;
+      if outcome == Outcome::Success {
+        return Ok(())
+      }
+      let interrupted = matches!(outcome, Outcome::Interrupted(_));
+      let err = ScuError::ExecutionFailed { name: name.to_string(), attempt, attempts, outcome };
+      if attempt == attempts || interrupted {
+        return Err(err)
+      }
+      self.warnings.borrow_mut().push(err);
+    }
+    Ok(())
   }
 
-  pub fn operate_many<'a, T>(&mut self, items: &'a mut [T], mut action: impl FnMut(&mut Controller, &'a mut T) -> Result<bool>) -> i32 {
-    items.into_iter().map(|item| action(self, item).map_err(|err| {
-      self.handle_error(&err);
-      err
-    }).unwrap_or(false) as i32).sum()
+  /// Warnings produced since the last call, like names used with --force despite a conflict
+  pub fn take_warnings(&self) -> Vec<ScuError> {
+    self.warnings.take()
   }
-}
\ No newline at end of file
+}
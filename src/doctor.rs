@@ -0,0 +1,171 @@
+use std::{fmt, path};
+
+use crate::{
+  controller::Controller,
+  shortcut::{ShortcutFile, ShortcutBody},
+  resource::ResourceSource,
+  interpreter::Interpreter,
+  errors::Result,
+  paths
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Status {
+  Pass,
+  Warn,
+  Fail
+}
+
+impl fmt::Display for Status {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Pass => write!(f, "pass"),
+      Self::Warn => write!(f, "warn"),
+      Self::Fail => write!(f, "fail"),
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct Check {
+  pub status: Status,
+  pub item: String,
+  pub fix: Option<String>
+}
+
+impl Check {
+  fn pass(item: impl ToString) -> Self {
+    Check { status: Status::Pass, item: item.to_string(), fix: None }
+  }
+
+  fn warn(item: impl ToString, fix: impl ToString) -> Self {
+    Check { status: Status::Warn, item: item.to_string(), fix: Some(fix.to_string()) }
+  }
+
+  fn fail(item: impl ToString, fix: impl ToString) -> Self {
+    Check { status: Status::Fail, item: item.to_string(), fix: Some(fix.to_string()) }
+  }
+}
+
+impl fmt::Display for Check {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "[{}] {}", self.status, self.item)?;
+    match &self.fix {
+      Some(fix) => write!(f, "\n |> Fix: {}", fix),
+      None => Ok(())
+    }
+  }
+}
+
+fn check_bin_dir(controller: &Controller) -> Check {
+  let bin = paths::stringify_default(controller.bin_dir());
+  if paths::in_path(controller.bin_dir()) {
+    Check::pass(format!("{} is in the PATH", bin))
+  } else {
+    Check::fail(format!("{} is not in the PATH", bin), "scu path install")
+  }
+}
+
+fn check_binary(shortcut: &ShortcutFile) -> Check {
+  let command = shortcut.command();
+  let binary = command.first().map(|x| x.as_str()).unwrap_or_default();
+  let found = if binary.contains(['/', '\\']) {
+    path::Path::new(binary).exists()
+  } else {
+    paths::find_executable(binary, None).is_some()
+  };
+  if found {
+    Check::pass(format!("{}: binary {} found", shortcut.name, binary))
+  } else {
+    Check::fail(
+      format!("{}: binary {} not found", shortcut.name, binary),
+      format!("Install {} or update the shortcut with: scu edit {}", binary, shortcut.name)
+    )
+  }
+}
+
+fn check_resource(shortcut: &ShortcutFile, path: &path::Path) -> Check {
+  if path.exists() {
+    Check::pass(format!("{}: resource {} exists", shortcut.name, path.display()))
+  } else {
+    Check::warn(format!("{}: resource {} is missing", shortcut.name, path.display()), format!("scu make {}", shortcut.name))
+  }
+}
+
+fn check_resources(shortcut: &ShortcutFile) -> Vec<Check> {
+  let mut checks = Vec::new();
+  if let ShortcutBody::CommandWithScript { script, .. } = &shortcut.body {
+    checks.push(check_resource(shortcut, script));
+  }
+  for resource in shortcut.resources.iter().flatten() {
+    let edit = format!("Update the resource with: scu edit {}", shortcut.name);
+    checks.push(match (&resource.source, resource.permissions()) {
+      (_, Err(err)) => Check::fail(format!("{}: {}", shortcut.name, err), edit),
+      (ResourceSource::Directory(source), _) if !source.is_dir() =>
+        Check::fail(format!("{}: resource directory {} not found", shortcut.name, source.display()), edit),
+      _ => check_resource(shortcut, &resource.path)
+    });
+  }
+  let resources = shortcut.resources.as_deref().unwrap_or_default();
+  for arg in shortcut.body.command() {
+    let unknown = arg.match_indices("{res:").map(|(start, _)| &arg[start..])
+      .filter_map(|rest| rest.find('}').map(|end| &rest[..=end]))
+      .filter(|placeholder| !resources.iter().any(|resource| resource.placeholder() == *placeholder));
+    for placeholder in unknown {
+      checks.push(Check::fail(
+        format!("{}: placeholder {} does not match any resource", shortcut.name, placeholder),
+        format!("Add the resource or fix the command with: scu edit {}", shortcut.name)
+      ));
+    }
+  }
+  checks
+}
+
+fn check_scripts(controller: &Controller, shortcut: &ShortcutFile) -> Vec<Check> {
+  let interpreters = shortcut.interpreters.clone().unwrap_or(Interpreter::all().to_vec());
+  let mut checks = Vec::new();
+  for interpreter in &interpreters {
+    for name in shortcut.names() {
+      let script = controller.script_path(name, interpreter);
+      let display = paths::stringify_default(&script);
+      checks.push(if !script.exists() {
+        Check::warn(format!("{}: script {} not generated", shortcut.name, display), format!("scu make {}", shortcut.name))
+      } else if !paths::is_executable(&script) {
+        Check::fail(format!("{}: script {} is not executable", shortcut.name, display), format!("scu make {}", shortcut.name))
+      } else {
+        Check::pass(format!("{}: script {} is executable", shortcut.name, display))
+      });
+    }
+  }
+  checks
+}
+
+fn check_startup(shortcut: &ShortcutFile) -> Option<Check> {
+  shortcut.startup.as_ref().map(|startup| if startup.path().exists() {
+    Check::pass(format!("{}: startup entry {} exists", shortcut.name, startup))
+  } else {
+    Check::fail(
+      format!("{}: startup entry {} is missing", shortcut.name, startup),
+      format!("scu startup -f {}", shortcut.name)
+    )
+  })
+}
+
+pub fn diagnose(controller: &Controller) -> Result<Vec<Check>> {
+  let mut checks = vec![check_bin_dir(controller)];
+  for (key, shortcut) in controller.get_all()? {
+    match shortcut {
+      Ok(shortcut) => {
+        checks.push(check_binary(&shortcut));
+        checks.extend(check_resources(&shortcut));
+        checks.extend(check_scripts(controller, &shortcut));
+        checks.extend(check_startup(&shortcut));
+      },
+      Err(err) => checks.push(Check::fail(
+        format!("{}", err),
+        format!("Fix the template or remove it with: scu unset -f {}", key)
+      ))
+    }
+  }
+  Ok(checks)
+}
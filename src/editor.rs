@@ -0,0 +1,131 @@
+use std::{env, fs, path, process};
+
+use crate::{errors::{Result, ScuError}, reader};
+
+const ERROR_PREFIX: &str = "# scu: ";
+
+/// First and last lines of the block of errors inserted at the top of the file, only that block is stripped
+const ERRORS_START: &str = "# scu: fix the errors below and save again, this block is removed";
+const ERRORS_END: &str = "# scu: end of errors";
+
+fn editor_command() -> Vec<String> {
+  ["VISUAL", "EDITOR"].into_iter()
+    .filter_map(|var| env::var(var).ok())
+    .map(|editor| editor.split_whitespace().map(|x| x.to_string()).collect::<Vec<String>>())
+    .find(|editor| !editor.is_empty())
+    .unwrap_or_else(|| vec![if cfg!(target_os = "windows") { "notepad" } else { "vi" }.to_string()])
+}
+
+pub fn open(file: impl AsRef<path::Path>) -> Result<()> {
+  let editor = editor_command();
+  let status = process::Command::new(&editor[0]).args(&editor[1..]).arg(file.as_ref()).status()?;
+  if status.success() {
+    Ok(())
+  } else {
+    Err(ScuError::EditCancelled { reason: format!("the editor exited with {}", status) })
+  }
+}
+
+fn strip_errors(content: String) -> String {
+  let mut lines = content.split_inclusive('\n');
+  if lines.next().map(str::trim_end) != Some(ERRORS_START) {
+    return content
+  }
+  let lines: Vec<&str> = lines.collect();
+  match lines.iter().position(|line| line.trim_end() == ERRORS_END) {
+    Some(end) => lines[end + 1..].concat(),
+    None => content
+  }
+}
+
+fn prepend_error(content: &str, err: &ScuError) -> String {
+  let message: String = err.to_string().lines().map(|line| format!("{}{}\n", ERROR_PREFIX, line)).collect();
+  format!("{}\n{}{}\n{}", ERRORS_START, message, ERRORS_END, content)
+}
+
+pub fn edit_validated<T>(
+  content: impl AsRef<str>,
+  file_name: impl AsRef<str>,
+  validate: impl Fn(&str) -> Result<T>
+) -> Result<T> {
+  // The file keeps its name for the editor, inside a directory only the user can access
+  let dir = tempfile::Builder::new().prefix("scu-").tempdir()?;
+  let file = dir.path().join(file_name.as_ref());
+  let mut content = content.as_ref().to_string();
+  let result = loop {
+    let edited = fs::write(&file, &content).map_err(ScuError::from)
+      .and_then(|_| open(&file))
+      .and_then(|_| reader::from_file(&file))
+      .map(strip_errors);
+    match edited {
+      Ok(edited) if edited.trim().is_empty() =>
+        break Err(ScuError::EditCancelled { reason: "the file is empty".into() }),
+      Ok(edited) => match validate(&edited) {
+        Ok(value) => break Ok(value),
+        Err(err) => content = prepend_error(&edited, &err)
+      },
+      Err(err) => break Err(err)
+    }
+  };
+  let _ = dir.close();
+  result
+}
+
+pub fn edit(content: impl AsRef<str>, file_name: impl AsRef<str>) -> Result<String> {
+  edit_validated(content, file_name, |data| Ok(data.to_string()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use super::*;
+  use std::sync::Mutex;
+  use tempfile::TempDir;
+
+  /// The editor is read from the environment, which the tests share
+  static EDITOR: Mutex<()> = Mutex::new(());
+
+  fn with_editor<T>(script: &str, run: impl FnOnce() -> T) -> T {
+    let _guard = EDITOR.lock().unwrap_or_else(|err| err.into_inner());
+    let dir = TempDir::new().unwrap();
+    let editor = dir.path().join("editor.sh");
+    fs::write(&editor, script).unwrap();
+    env::set_var("VISUAL", format!("sh {}", editor.display()));
+    let result = run();
+    env::remove_var("VISUAL");
+    result
+  }
+
+  fn reject_todo(data: &str) -> Result<String> {
+    match data.contains("todo") {
+      true => Err(ScuError::StringError("Still a todo".into())),
+      false => Ok(data.to_string())
+    }
+  }
+
+  #[test]
+  fn invalid_content_is_reopened_with_the_errors() {
+    let script = "if grep -q '^# scu: Still a todo' \"$1\"; then sed -i 's/todo/done/' \"$1\"; fi\n";
+    let edited = with_editor(script, || edit_validated("# scu: kept\ntodo\n", "file.sh", reject_todo));
+    assert_eq!(edited.unwrap(), "# scu: kept\ndone\n");
+  }
+
+  #[test]
+  fn empty_content_cancels_the_edit() {
+    let edited = with_editor(": > \"$1\"\n", || edit("content\n", "file.toml"));
+    assert!(edited.is_err());
+  }
+
+  #[test]
+  fn failing_editor_cancels_the_edit() {
+    let edited = with_editor("exit 1\n", || edit_validated("todo\n", "file.toml", reject_todo));
+    assert!(edited.is_err());
+  }
+
+  #[test]
+  fn only_the_inserted_errors_are_stripped() {
+    let content = prepend_error("# scu: kept\nbody\n", &ScuError::StringError("first\nsecond".into()));
+    assert_eq!(content.lines().filter(|line| line.starts_with(ERROR_PREFIX)).count(), 5);
+    assert_eq!(strip_errors(content), "# scu: kept\nbody\n");
+    assert_eq!(strip_errors("# scu: kept\n".into()), "# scu: kept\n");
+  }
+}
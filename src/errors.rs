@@ -1,29 +1,48 @@
-macro_rules! scu_enum_err {
-  {$($variant:ident($err:path)),*} => {
-    #[derive(Debug)]
-    pub enum ScuError {
-      $($variant($err),)*
-    }
+use std::{fmt, path, error::Error};
+
+use crate::{execution::Outcome, interpreter::Interpreter};
+
+#[derive(Debug)]
+pub enum ScuError {
+  IoError(std::io::Error),
+  DeserializeError(toml::de::Error),
+  SerializeError(toml::ser::Error),
+  Utf8Error(std::string::FromUtf8Error),
+  StringError(String),
+  ShortcutNotFound { name: String, suggestions: Vec<String> },
+  UnknownInterpreter { name: String, suggestions: Vec<String> },
+  InvalidShortcut { path: path::PathBuf, source: toml::de::Error },
+  ResourceError { path: path::PathBuf, source: Option<std::io::Error> },
+  NameError { name: String, reason: String },
+  EmptyCommand,
+  ChecksFailed { count: usize },
+  Locked { path: path::PathBuf },
+  GitError { args: String, message: String },
+  SyncNotInitialized,
+  ExecutionFailed { name: String, attempt: u32, attempts: u32, outcome: Outcome },
+  InvalidMode { resource: String, mode: String },
+  NotPortable { name: String, resource: String },
+  HomeNotFound,
+  EditCancelled { reason: String },
+  NothingToUndo,
+  HistoryEntryNotFound { sequence: u64 },
+  HistoryEntryMismatch { sequence: u64, name: String },
+  InvalidHistoryEntry { path: path::PathBuf, reason: Box<ScuError> },
+  Unsupported { feature: &'static str },
+  LimitsIgnored { name: String, interpreter: Interpreter }
+}
 
+macro_rules! scu_err_from {
+  {$($variant:ident($err:path)),*} => {
     $(impl From<$err> for ScuError {
       fn from(value: $err) -> Self {
         ScuError::$variant(value)
       }
     })*
-
-    impl std::fmt::Display for ScuError {
-      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-          $(
-            Self::$variant(err) => err.fmt(f),  
-          )*
-        }
-      }
-    }
   };
 }
 
-scu_enum_err! {
+scu_err_from! {
   IoError(std::io::Error),
   DeserializeError(toml::de::Error),
   SerializeError(toml::ser::Error),
@@ -31,4 +50,110 @@ scu_enum_err! {
   StringError(String)
 }
 
-pub type Result<T> = std::result::Result<T, ScuError>;
\ No newline at end of file
+impl ScuError {
+  pub fn name_error(name: impl AsRef<str>, reason: impl ToString) -> Self {
+    ScuError::NameError { name: name.as_ref().to_string(), reason: reason.to_string() }
+  }
+
+  /// Process exit code for the error category, 2 is left to the argument parser
+  pub fn exit_code(&self) -> i32 {
+    match self {
+      Self::StringError(_) => 1,
+      Self::ShortcutNotFound { .. } => 3,
+      Self::UnknownInterpreter { .. } => 4,
+      Self::InvalidShortcut { .. } | Self::DeserializeError(_) => 5,
+      Self::NameError { .. } => 6,
+      Self::ResourceError { .. } | Self::InvalidMode { .. } | Self::NotPortable { .. } => 7,
+      Self::EmptyCommand => 8,
+      Self::ChecksFailed { .. } => 9,
+      Self::IoError(_) | Self::Utf8Error(_) | Self::SerializeError(_) | Self::HomeNotFound => 10,
+      Self::Locked { .. } => 11,
+      Self::GitError { .. } | Self::SyncNotInitialized => 12,
+      Self::ExecutionFailed { .. } => 13,
+      Self::EditCancelled { .. } => 14,
+      Self::NothingToUndo | Self::HistoryEntryNotFound { .. } | Self::HistoryEntryMismatch { .. }
+        | Self::InvalidHistoryEntry { .. } => 15,
+      Self::Unsupported { .. } | Self::LimitsIgnored { .. } => 16,
+    }
+  }
+}
+
+fn did_you_mean(suggestions: &[String]) -> String {
+  if suggestions.is_empty() {
+    String::new()
+  } else {
+    format!(", did you mean: {}?", suggestions.join(", "))
+  }
+}
+
+impl fmt::Display for ScuError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::IoError(err) => err.fmt(f),
+      Self::DeserializeError(err) => err.fmt(f),
+      Self::SerializeError(err) => err.fmt(f),
+      Self::Utf8Error(err) => err.fmt(f),
+      Self::StringError(err) => err.fmt(f),
+      Self::ShortcutNotFound { name, suggestions } =>
+        write!(f, "Shortcut not found: {}{}", name, did_you_mean(suggestions)),
+      Self::UnknownInterpreter { name, suggestions } =>
+        write!(f, "Interpreter not registered: {}{}", name, did_you_mean(suggestions)),
+      Self::InvalidShortcut { path, .. } => write!(f, "Invalid shortcut file: {}", path.display()),
+      Self::ResourceError { path, .. } => write!(f, "Unable to use resource: {}", path.display()),
+      Self::NameError { name, reason } => write!(f, "Name {}: {}", name, reason),
+      Self::EmptyCommand => write!(f, "Expecting at least one element in the command"),
+      Self::ChecksFailed { count } => write!(f, "{} check{} failed", count, if *count == 1 { "" } else { "s" }),
+      Self::GitError { args, message } => write!(f, "Command git {} failed: {}", args, message),
+      Self::SyncNotInitialized => write!(f, "Synchronisation not initialized, use: scu sync init <remote>"),
+      Self::ExecutionFailed { name, attempt, attempts, outcome } =>
+        write!(f, "Shortcut {} {} on attempt {} of {}", name, outcome, attempt, attempts),
+      Self::InvalidMode { resource, mode } =>
+        write!(f, "Invalid mode {} for resource {}, expecting octal digits like 755", mode, resource),
+      Self::NotPortable { name, resource } => write!(
+        f, "Resource {} of {} is copied from a directory of this machine and can not be synchronised, share it with: scu bundle export",
+        resource, name
+      ),
+      Self::HomeNotFound => write!(f, "Unable to find the home directory"),
+      Self::EditCancelled { reason } => write!(f, "Edit cancelled: {}", reason),
+      Self::NothingToUndo => write!(f, "Nothing to undo"),
+      Self::HistoryEntryNotFound { sequence } => write!(f, "History entry #{} not found", sequence),
+      Self::HistoryEntryMismatch { sequence, name } => write!(f, "History entry #{} does not change {}", sequence, name),
+      Self::InvalidHistoryEntry { path, reason } => write!(f, "Invalid history entry {}: {}", path.display(), reason),
+      Self::Unsupported { feature } => write!(f, "{} is not supported on this system", feature),
+      Self::LimitsIgnored { name, interpreter } => write!(
+        f, "The {} script of {} ignores its timeout and retries, only scu run, bash and python scripts apply them", interpreter.name(), name
+      ),
+      Self::Locked { path } => write!(
+        f, "Another scu process is modifying the shortcuts, remove {} if it is not running", path.display()
+      ),
+    }
+  }
+}
+
+impl Error for ScuError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      Self::IoError(err) => Some(err),
+      Self::DeserializeError(err) => Some(err),
+      Self::SerializeError(err) => Some(err),
+      Self::Utf8Error(err) => Some(err),
+      Self::InvalidShortcut { source, .. } => Some(source),
+      Self::ResourceError { source: Some(source), .. } => Some(source),
+      _ => None
+    }
+  }
+}
+
+pub type Result<T> = std::result::Result<T, ScuError>;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn wrapped_errors_are_the_source() {
+    let err = ScuError::from(std::io::Error::new(std::io::ErrorKind::NotFound, "missing"));
+    assert_eq!(err.source().map(|source| source.to_string()), Some("missing".to_string()));
+    assert!(ScuError::NothingToUndo.source().is_none());
+  }
+}
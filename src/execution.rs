@@ -0,0 +1,251 @@
+use std::{fmt, io, process, thread, time::{Duration, Instant}};
+#[cfg(unix)]
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// Wait before the first retry, it doubles on every following one
+pub const BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest wait between two attempts
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Interval the child is polled at while a timeout is running
+const POLL: Duration = Duration::from_millis(20);
+
+/// Limits a shortcut is run with, the timeout applies to every attempt
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Execution {
+  pub timeout: Option<Duration>,
+  pub retries: u32
+}
+
+/// How an attempt ended
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+  Success,
+  Failed(Option<i32>),
+  TimedOut(Duration),
+  /// Stopped by the user with the given signal, it is not retried
+  Interrupted(i32)
+}
+
+impl Execution {
+  pub fn is_empty(&self) -> bool {
+    *self == Execution::default()
+  }
+
+  pub fn attempts(&self) -> u32 {
+    self.retries.saturating_add(1)
+  }
+
+  /// Wait before the given attempt, counting from 1
+  pub fn backoff(attempt: u32) -> Duration {
+    match attempt {
+      0 | 1 => Duration::ZERO,
+      _ => BACKOFF.saturating_mul(2u32.saturating_pow(attempt - 2)).min(MAX_BACKOFF)
+    }
+  }
+
+  /// Runs the command once, on timeout the whole process group of the child is killed.
+  ///
+  /// With a timeout the child runs in its own process group, on unix it is given the terminal when scu has it
+  /// and the interrupt, termination and hangup signals scu receives are forwarded to it.
+  /// Suspending it from the terminal is not supported, scu keeps waiting for it.
+  pub fn attempt(&self, command: &mut process::Command) -> io::Result<Outcome> {
+    let timeout = match self.timeout {
+      Some(timeout) => timeout,
+      None => return command.status().map(Outcome::from)
+    };
+    isolate(command);
+    let mut child = command.spawn()?;
+    let mut group = Group::enter(&child);
+    let start = Instant::now();
+    loop {
+      if let Some(status) = child.try_wait()? {
+        return Ok(group.interrupted(&status).map(Outcome::Interrupted).unwrap_or(status.into()))
+      }
+      group.forward();
+      if start.elapsed() >= timeout {
+        kill_group(&mut child)?;
+        child.wait()?;
+        return Ok(Outcome::TimedOut(timeout))
+      }
+      thread::sleep(POLL);
+    }
+  }
+}
+
+impl From<process::ExitStatus> for Outcome {
+  fn from(status: process::ExitStatus) -> Self {
+    if status.success() { Outcome::Success } else { Outcome::Failed(status.code()) }
+  }
+}
+
+impl fmt::Display for Outcome {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Success => write!(f, "succeeded"),
+      Self::Failed(Some(code)) => write!(f, "exited with code {}", code),
+      Self::Failed(None) => write!(f, "was terminated by a signal"),
+      Self::TimedOut(timeout) => write!(f, "timed out after {}s", timeout.as_secs_f32()),
+      Self::Interrupted(signal) => write!(f, "was interrupted by signal {}", signal)
+    }
+  }
+}
+
+#[cfg(unix)]
+fn isolate(command: &mut process::Command) {
+  use std::os::unix::process::CommandExt;
+  command.process_group(0);
+}
+
+#[cfg(windows)]
+fn isolate(_: &mut process::Command) {}
+
+/// Last signal scu received while an attempt runs in its own process group
+#[cfg(unix)]
+static RECEIVED: AtomicI32 = AtomicI32::new(0);
+
+#[cfg(unix)]
+const FORWARDED: [libc::c_int; 3] = [libc::SIGINT, libc::SIGTERM, libc::SIGHUP];
+
+#[cfg(unix)]
+extern "C" fn receive(signal: libc::c_int) {
+  RECEIVED.store(signal, Ordering::SeqCst);
+}
+
+/// Process group of a running attempt, the terminal and the signal handlers are given back when dropped
+#[cfg(unix)]
+struct Group {
+  id: libc::pid_t,
+  handlers: Vec<(libc::c_int, libc::sighandler_t)>,
+  /// Group of scu when the terminal was handed to the attempt
+  terminal: Option<libc::pid_t>,
+  forwarded: Option<i32>
+}
+
+#[cfg(unix)]
+impl Group {
+  fn enter(child: &process::Child) -> Self {
+    let id = child.id() as libc::pid_t;
+    RECEIVED.store(0, Ordering::SeqCst);
+    unsafe {
+      // Also done by the child, setting it here ensures the group exists before it gets the terminal
+      libc::setpgid(id, id);
+      let handlers = FORWARDED.iter()
+        .map(|&signal| (signal, libc::signal(signal, receive as extern "C" fn(libc::c_int) as libc::sighandler_t)))
+        .collect();
+      let own = libc::getpgrp();
+      let terminal = (libc::isatty(libc::STDIN_FILENO) == 1 && libc::tcgetpgrp(libc::STDIN_FILENO) == own
+        && libc::tcsetpgrp(libc::STDIN_FILENO, id) == 0).then_some(own);
+      Group { id, handlers, terminal, forwarded: None }
+    }
+  }
+
+  /// Sends the signal received since the last call to the group
+  fn forward(&mut self) {
+    let signal = RECEIVED.swap(0, Ordering::SeqCst);
+    if signal != 0 {
+      unsafe { libc::kill(-self.id, signal) };
+      self.forwarded = Some(signal);
+    }
+  }
+
+  /// Signal the attempt was stopped with by the user, from the terminal it reaches the group directly
+  fn interrupted(&mut self, status: &process::ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    self.forward();
+    self.forwarded.or(status.signal().filter(|signal| FORWARDED.contains(signal)))
+  }
+}
+
+#[cfg(unix)]
+impl Drop for Group {
+  fn drop(&mut self) {
+    unsafe {
+      if let Some(own) = self.terminal {
+        // Taking the terminal back from the background would stop scu with SIGTTOU
+        let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+        libc::tcsetpgrp(libc::STDIN_FILENO, own);
+        libc::signal(libc::SIGTTOU, previous);
+      }
+      for (signal, handler) in &self.handlers {
+        libc::signal(*signal, *handler);
+      }
+    }
+  }
+}
+
+#[cfg(windows)]
+struct Group;
+
+#[cfg(windows)]
+impl Group {
+  fn enter(_: &process::Child) -> Self {
+    Group
+  }
+
+  fn forward(&mut self) {}
+
+  fn interrupted(&mut self, _: &process::ExitStatus) -> Option<i32> {
+    None
+  }
+}
+
+#[cfg(unix)]
+fn kill_group(child: &mut process::Child) -> io::Result<()> {
+  // The child leads its own group, so its id is the id of the group
+  match unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) } {
+    0 => Ok(()),
+    _ => child.kill()
+  }
+}
+
+#[cfg(windows)]
+fn kill_group(child: &mut process::Child) -> io::Result<()> {
+  let status = process::Command::new("taskkill").args(["/T", "/F", "/PID"]).arg(child.id().to_string())
+    .stdout(process::Stdio::null()).stderr(process::Stdio::null()).status()?;
+  if status.success() { Ok(()) } else { child.kill() }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn backoff_doubles_up_to_the_limit() {
+    assert_eq!(Execution::backoff(1), Duration::ZERO);
+    assert_eq!(Execution::backoff(2), BACKOFF);
+    assert_eq!(Execution::backoff(3), BACKOFF * 2);
+    assert_eq!(Execution::backoff(4), BACKOFF * 4);
+    assert_eq!(Execution::backoff(40), MAX_BACKOFF);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn timeout_kills_the_process_group() {
+    let execution = Execution { timeout: Some(Duration::from_millis(200)), retries: 0 };
+    let start = Instant::now();
+    let mut command = process::Command::new("sh");
+    command.args(["-c", "sleep 30 & sleep 30; wait"]);
+    assert_eq!(execution.attempt(&mut command).unwrap(), Outcome::TimedOut(Duration::from_millis(200)));
+    assert!(start.elapsed() < Duration::from_secs(10));
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn interrupted_attempts_are_reported() {
+    let execution = Execution { timeout: Some(Duration::from_secs(10)), retries: 0 };
+    let mut command = process::Command::new("sh");
+    command.args(["-c", "kill -INT $$; sleep 10"]);
+    assert_eq!(execution.attempt(&mut command).unwrap(), Outcome::Interrupted(libc::SIGINT));
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn failures_keep_the_exit_code() {
+    let execution = Execution::default();
+    let mut command = process::Command::new("sh");
+    command.args(["-c", "exit 4"]);
+    assert_eq!(execution.attempt(&mut command).unwrap(), Outcome::Failed(Some(4)));
+  }
+}
@@ -0,0 +1,213 @@
+use serde::{Serialize, Deserialize};
+use std::{fs, path, time::{SystemTime, UNIX_EPOCH}};
+
+use crate::{shortcut::Shortcut, errors::{Result, ScuError}, paths};
+
+const MAX_ENTRIES: usize = 100;
+const SUFFIX: &str = ".toml";
+/// File keeping the last sequence number given, so numbers are not reused after an undo
+const SEQUENCE_FILE: &str = "sequence";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Change {
+  pub name: String,
+  /// Stored with its schema version and upgraded when read, like the templates
+  #[serde(default, with = "versioned")]
+  pub previous: Option<Shortcut>
+}
+
+mod versioned {
+  use serde::{Serialize, Serializer, Deserialize, Deserializer, de, ser};
+
+  use crate::{shortcut::Shortcut, migration};
+
+  pub fn serialize<S: Serializer>(shortcut: &Option<Shortcut>, serializer: S) -> Result<S::Ok, S::Error> {
+    shortcut.as_ref().map(migration::to_table).transpose().map_err(ser::Error::custom)?.serialize(serializer)
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Shortcut>, D::Error> {
+    Option::<toml::Table>::deserialize(deserializer)?
+      .map(|table| migration::from_table(table).map(|(shortcut, _)| shortcut))
+      .transpose()
+      .map_err(de::Error::custom)
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Entry {
+  pub sequence: u64,
+  pub timestamp: u64,
+  pub action: String,
+  pub changes: Vec<Change>
+}
+
+impl Entry {
+  pub fn affects(&self, name: impl AsRef<str>) -> bool {
+    self.changes.iter().any(|change| change.name == name.as_ref())
+  }
+
+  pub fn names(&self) -> Vec<&str> {
+    self.changes.iter().map(|change| change.name.as_str()).collect()
+  }
+
+  pub fn elapsed(&self) -> String {
+    ago(self.timestamp)
+  }
+}
+
+/// Seconds since the unix epoch
+pub fn now() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or_default()
+}
+
+/// Time passed since the timestamp in its largest unit, like `3h ago`
+pub fn ago(timestamp: u64) -> String {
+  let seconds = now().saturating_sub(timestamp);
+  match seconds {
+    0..=59 => format!("{}s ago", seconds),
+    60..=3599 => format!("{}m ago", seconds / 60),
+    3600..=86399 => format!("{}h ago", seconds / 3600),
+    _ => format!("{}d ago", seconds / 86400)
+  }
+}
+
+/// Entries of the history sorted by sequence
+pub struct Journal {
+  pub entries: Vec<Entry>,
+  /// File name of the entries that could not be read with the reason
+  pub invalid: Vec<(String, ScuError)>
+}
+
+pub struct History {
+  dir: path::PathBuf
+}
+
+impl History {
+  pub fn new(dir: path::PathBuf) -> Self {
+    History { dir }
+  }
+
+  fn entry_path(&self, sequence: u64) -> path::PathBuf {
+    self.dir.join(format!("{:08}{}", sequence, SUFFIX))
+  }
+
+  /// Every entry that can be read, a damaged file does not hide the rest
+  pub fn entries(&self) -> Result<Journal> {
+    let mut journal = Journal { entries: Vec::new(), invalid: Vec::new() };
+    for entry in fs::read_dir(&self.dir)?.filter_map(|x| x.ok()) {
+      let file_name = entry.file_name().to_string_lossy().to_string();
+      if file_name.ends_with(SUFFIX) {
+        match fs::read_to_string(entry.path()).map_err(ScuError::from)
+          .and_then(|data| toml::from_str::<Entry>(&data).map_err(ScuError::from)) {
+          Ok(entry) => journal.entries.push(entry),
+          Err(err) => journal.invalid.push((file_name, err))
+        }
+      }
+    }
+    journal.entries.sort_by_key(|entry| entry.sequence);
+    journal.invalid.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(journal)
+  }
+
+  pub fn get(&self, sequence: u64) -> Result<Entry> {
+    self.entries()?.entries.into_iter().find(|entry| entry.sequence == sequence)
+      .ok_or(ScuError::HistoryEntryNotFound { sequence })
+  }
+
+  fn last_sequence(&self, entries: &[Entry]) -> u64 {
+    let stored = fs::read_to_string(self.dir.join(SEQUENCE_FILE)).ok()
+      .and_then(|sequence| sequence.trim().parse().ok()).unwrap_or(0);
+    entries.iter().map(|entry| entry.sequence).fold(stored, u64::max)
+  }
+
+  pub fn record(&self, action: impl AsRef<str>, changes: Vec<Change>) -> Result<()> {
+    let entries = self.entries()?.entries;
+    let entry = Entry {
+      sequence: self.last_sequence(&entries) + 1,
+      timestamp: now(),
+      action: action.as_ref().to_string(),
+      changes
+    };
+    paths::write_atomic(self.entry_path(entry.sequence), toml::to_string_pretty(&entry)?, false)?;
+    paths::write_atomic(self.dir.join(SEQUENCE_FILE), entry.sequence.to_string(), false)?;
+    for old in entries.iter().rev().skip(MAX_ENTRIES - 1) {
+      self.remove(old.sequence)?;
+    }
+    Ok(())
+  }
+
+  pub fn remove(&self, sequence: u64) -> Result<()> {
+    fs::remove_file(self.entry_path(sequence)).map_err(|err| err.into())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{shortcut::ShortcutBody, migration};
+  use tempfile::TempDir;
+
+  const UNVERSIONED: &str = r#"
+sequence = 3
+timestamp = 0
+action = "set"
+
+[[changes]]
+name = "report"
+
+[changes.previous]
+name = "report"
+
+[changes.previous.body]
+type = "CommandWithScript"
+
+[changes.previous.body.command]
+cmd = ["python"]
+script = "/data/scu_data/res/report.py"
+body = "print('report')"
+"#;
+
+  fn change(name: &str) -> Change {
+    let shortcut = Shortcut::builder().name(name).command(vec!["echo".into(), name.into()]).build();
+    Change { name: name.to_string(), previous: Some(shortcut) }
+  }
+
+  #[test]
+  fn previous_templates_are_stored_versioned_and_upgraded() {
+    let dir = TempDir::new().unwrap();
+    let history = History::new(dir.path().to_path_buf());
+    fs::write(history.entry_path(3), UNVERSIONED).unwrap();
+    let entry = history.get(3).unwrap();
+    match &entry.changes[0].previous.as_ref().unwrap().body {
+      ShortcutBody::CommandWithScript { script, .. } => assert_eq!(script, path::Path::new("/data/scu_data/res/report/report.py")),
+      body => panic!("unexpected body {:?}", body)
+    }
+    history.record("edit", vec![change("greet")]).unwrap();
+    let stored = fs::read_to_string(history.entry_path(4)).unwrap();
+    assert!(stored.contains(&format!("version = {}", migration::CURRENT_VERSION)));
+  }
+
+  #[test]
+  fn damaged_entries_are_reported_apart() {
+    let dir = TempDir::new().unwrap();
+    let history = History::new(dir.path().to_path_buf());
+    history.record("set", vec![change("a")]).unwrap();
+    fs::write(history.entry_path(2), "sequence = ").unwrap();
+    let journal = history.entries().unwrap();
+    assert_eq!(journal.entries.len(), 1);
+    assert_eq!(journal.invalid.len(), 1);
+    assert_eq!(journal.invalid[0].0, "00000002.toml");
+  }
+
+  #[test]
+  fn sequence_numbers_are_not_reused() {
+    let dir = TempDir::new().unwrap();
+    let history = History::new(dir.path().to_path_buf());
+    history.record("set", vec![change("a")]).unwrap();
+    history.record("set", vec![change("b")]).unwrap();
+    history.remove(2).unwrap();
+    history.record("set", vec![change("c")]).unwrap();
+    let sequences: Vec<u64> = history.entries().unwrap().entries.iter().map(|entry| entry.sequence).collect();
+    assert_eq!(sequences, vec![1, 3]);
+  }
+}
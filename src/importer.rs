@@ -0,0 +1,319 @@
+use std::{fs, path};
+
+use clap::ValueEnum;
+
+use crate::{shortcut::Shortcut, interpreter::Interpreter, errors::Result, reader};
+
+#[derive(Clone, Debug, ValueEnum)]
+pub enum ImportSource {
+  BashAliases,
+  Binclude
+}
+
+#[derive(Debug)]
+pub struct Rejected {
+  pub origin: String,
+  pub reason: &'static str
+}
+
+#[derive(Debug, Default)]
+pub struct Imported {
+  pub shortcuts: Vec<Shortcut>,
+  pub rejected: Vec<Rejected>
+}
+
+impl Imported {
+  fn accept(
+    &mut self,
+    origin: impl ToString,
+    name: impl AsRef<str>,
+    command: Vec<String>,
+    interpreters: &Option<Vec<Interpreter>>
+  ) {
+    self.forget(&name);
+    // A shortcut named after the command it calls would run itself once its bin directory comes first in the PATH
+    if command.first().is_some_and(|binary| binary == name.as_ref()) {
+      self.reject(origin, "calls itself")
+    } else {
+      self.shortcuts.push(
+        Shortcut::builder().name(name).interpreters(interpreters.clone()).command(command).build()
+      )
+    }
+  }
+
+  /// Like the shell, the last definition of a name replaces the previous ones even when it can not be imported
+  fn forget(&mut self, name: impl AsRef<str>) {
+    self.shortcuts.retain(|shortcut| shortcut.name != name.as_ref());
+  }
+
+  fn reject(&mut self, origin: impl ToString, reason: &'static str) {
+    self.rejected.push(Rejected { origin: origin.to_string(), reason })
+  }
+}
+
+const SHELL_FEATURES: [&str; 7] = ["|", "&", ";", ">", "<", "$", "`"];
+
+fn split_words(line: &str, escapes: bool) -> Option<Vec<String>> {
+  let mut words = Vec::new();
+  let mut current = String::new();
+  let mut in_word = false;
+  let mut chars = line.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '\'' => loop {
+        in_word = true;
+        match chars.next()? {
+          '\'' => break,
+          c => current.push(c)
+        }
+      },
+      '"' => loop {
+        in_word = true;
+        match chars.next()? {
+          '"' => break,
+          '\\' if escapes => match chars.next()? {
+            c @ ('$' | '`' | '"' | '\\') => current.push(c),
+            c => current.extend(['\\', c])
+          },
+          c => current.push(c)
+        }
+      },
+      '\\' if escapes => {
+        in_word = true;
+        current.push(chars.next()?)
+      },
+      '#' if !in_word => break,
+      c if c.is_whitespace() => if in_word {
+        words.push(std::mem::take(&mut current));
+        in_word = false
+      },
+      c => {
+        in_word = true;
+        current.push(c)
+      }
+    }
+  }
+  if in_word {
+    words.push(current)
+  }
+  Some(words)
+}
+
+fn parse_command(command: &str, escapes: bool) -> std::result::Result<Vec<String>, &'static str> {
+  if SHELL_FEATURES.iter().any(|feature| command.contains(feature)) {
+    return Err("uses shell features")
+  }
+  match split_words(command, escapes) {
+    Some(words) if words.is_empty() => Err("empty command"),
+    Some(words) => Ok(words),
+    None => Err("unterminated quote")
+  }
+}
+
+fn parse_function(line: &str) -> Option<std::result::Result<(String, Vec<String>), &'static str>> {
+  let line = line.strip_prefix("function ").unwrap_or(line).trim();
+  let name_end = line.find(|c: char| c == '(' || c.is_whitespace() || c == '{')?;
+  let (name, rest) = line.split_at(name_end);
+  let rest = rest.trim_start().strip_prefix("()").unwrap_or(rest).trim();
+  if name.is_empty() || !rest.starts_with('{') {
+    return None
+  }
+  let body = match rest.strip_prefix('{').and_then(|body| body.strip_suffix('}')) {
+    Some(body) => body.trim().trim_end_matches(';').trim_end(),
+    None => return Some(Err("multiline function"))
+  };
+  let body = body.strip_suffix("\"$@\"").or(body.strip_suffix("$@")).unwrap_or(body);
+  Some(parse_command(body, true).map(|command| (name.to_string(), command)))
+}
+
+fn import_bash_aliases(source: &path::Path, interpreters: &Option<Vec<Interpreter>>) -> Result<Imported> {
+  let mut imported = Imported::default();
+  for (number, line) in reader::from_file(source)?.lines().enumerate() {
+    let trimmed = line.trim();
+    let origin = format!("line {}: {}", number + 1, trimmed);
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+      continue
+    }
+    if let Some(definitions) = trimmed.strip_prefix("alias ") {
+      let definitions = match split_words(definitions, true) {
+        Some(definitions) => definitions,
+        None => {
+          imported.reject(origin, "unterminated quote");
+          continue
+        }
+      };
+      for definition in definitions.iter().filter(|definition| !definition.starts_with('-')) {
+        match definition.split_once('=').map(|(name, command)| (name, parse_command(command, true))) {
+          Some((name, Ok(command))) => imported.accept(&origin, name, command, interpreters),
+          Some((name, Err(reason))) => {
+            imported.forget(name);
+            imported.reject(&origin, reason)
+          },
+          None => imported.reject(&origin, "alias without definition")
+        }
+      }
+    } else if let Some(function) = parse_function(trimmed) {
+      match function {
+        Ok((name, command)) => imported.accept(origin, name, command, interpreters),
+        Err(reason) => imported.reject(origin, reason)
+      }
+    }
+  }
+  Ok(imported)
+}
+
+fn binclude_command(interpreter: &Interpreter, content: &str) -> std::result::Result<Vec<String>, &'static str> {
+  let (forward, escapes) = match interpreter {
+    Interpreter::Bash => (["\"$@\"", "$@"].as_slice(), true),
+    Interpreter::Cmd | Interpreter::Batch => (["%*"].as_slice(), false),
+    _ => return Err("unsupported interpreter")
+  };
+  content.lines().map(|line| line.trim())
+    .find_map(|line| forward.iter().find_map(|forward| line.strip_suffix(forward)))
+    .ok_or("command not found")
+    .and_then(|command| parse_command(command, escapes))
+}
+
+fn import_binclude(source: &path::Path, interpreters: &Option<Vec<Interpreter>>) -> Result<Imported> {
+  let mut imported = Imported::default();
+  let mut entries: Vec<path::PathBuf> = if source.is_dir() {
+    fs::read_dir(source)?.filter_map(|x| x.ok()).map(|entry| entry.path()).filter(|path| path.is_file()).collect()
+  } else {
+    vec![source.to_path_buf()]
+  };
+  entries.sort();
+  for entry in entries {
+    let origin = entry.display().to_string();
+    let name = entry.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let interpreter = match entry.extension() {
+      Some(extension) => Interpreter::from_extension(format!(".{}", extension.to_string_lossy())),
+      None => Some(Interpreter::Bash)
+    };
+    let command = interpreter.ok_or("unknown extension")
+      .and_then(|interpreter| match reader::from_file(&entry) {
+        Ok(content) => binclude_command(&interpreter, &content),
+        Err(_) => Err("unreadable file")
+      });
+    match command {
+      Ok(command) => imported.accept(origin, name, command, interpreters),
+      Err(reason) => imported.reject(origin, reason)
+    }
+  }
+  Ok(imported)
+}
+
+pub fn import(
+  from: &ImportSource,
+  source: impl AsRef<path::Path>,
+  interpreters: &Option<Vec<Interpreter>>
+) -> Result<Imported> {
+  match from {
+    ImportSource::BashAliases => import_bash_aliases(source.as_ref(), interpreters),
+    ImportSource::Binclude => import_binclude(source.as_ref(), interpreters)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  fn words(words: &[&str]) -> Vec<String> {
+    words.iter().map(|word| word.to_string()).collect()
+  }
+
+  fn import_aliases(content: &str) -> Imported {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join(".bash_aliases");
+    fs::write(&file, content).unwrap();
+    import(&ImportSource::BashAliases, file, &None).unwrap()
+  }
+
+  fn commands(imported: &Imported) -> Vec<(String, Vec<String>)> {
+    imported.shortcuts.iter().map(|shortcut| (shortcut.name.clone(), shortcut.command())).collect()
+  }
+
+  #[test]
+  fn alias_lines_are_split_into_words() {
+    let imported = import_aliases(concat!(
+      "# comment\n",
+      "alias ll='ls -la' la=\"ls -A\"\n",
+      "alias -- gs='git status' # trailing comment\n",
+      "alias say=\"echo \\\"hi there\\\"\"\n"
+    ));
+    assert_eq!(commands(&imported), vec![
+      ("ll".to_string(), words(&["ls", "-la"])),
+      ("la".to_string(), words(&["ls", "-A"])),
+      ("gs".to_string(), words(&["git", "status"])),
+      ("say".to_string(), words(&["echo", "hi there"]))
+    ]);
+    assert!(imported.rejected.is_empty());
+  }
+
+  #[test]
+  fn redefined_aliases_keep_the_last_definition() {
+    let imported = import_aliases(concat!(
+      "alias ll='ls -l'\nalias la='ls -A'\nalias lg='ls'\n",
+      "alias ll='ls -la'\nalias la='la'\nalias lg='ls | grep'\n"
+    ));
+    assert_eq!(commands(&imported), vec![("ll".to_string(), words(&["ls", "-la"]))]);
+    assert_eq!(imported.rejected.len(), 2);
+  }
+
+  #[test]
+  fn unsupported_aliases_are_rejected() {
+    let imported = import_aliases(concat!(
+      "alias grep='grep --color=auto'\n",
+      "alias logs='tail -f log | grep error'\n",
+      "alias broken='echo\n",
+      "alias empty=\n"
+    ));
+    assert!(imported.shortcuts.is_empty());
+    let reasons: Vec<&str> = imported.rejected.iter().map(|rejected| rejected.reason).collect();
+    assert_eq!(reasons, vec!["calls itself", "uses shell features", "unterminated quote", "empty command"]);
+    assert_eq!(imported.rejected[0].origin, "line 1: alias grep='grep --color=auto'");
+  }
+
+  #[test]
+  fn one_line_functions_forward_their_arguments() {
+    let imported = import_aliases(concat!(
+      "mkcd() { mkdir -p \"$@\"; }\n",
+      "function serve { python3 -m http.server $@; }\n",
+      "ls() { ls --color \"$@\"; }\n",
+      "multi() {\n",
+      "  echo multi\n",
+      "}\n"
+    ));
+    assert_eq!(commands(&imported), vec![
+      ("mkcd".to_string(), words(&["mkdir", "-p"])),
+      ("serve".to_string(), words(&["python3", "-m", "http.server"]))
+    ]);
+    let reasons: Vec<&str> = imported.rejected.iter().map(|rejected| rejected.reason).collect();
+    assert_eq!(reasons, vec!["calls itself", "multiline function"]);
+  }
+
+  #[test]
+  fn binclude_scripts_keep_the_forwarding_line() {
+    let bash = "#!/bin/bash\n# generated\nnode \"/opt/tools/cli.js\" --quiet \"$@\"\n";
+    assert_eq!(binclude_command(&Interpreter::Bash, bash), Ok(words(&["node", "/opt/tools/cli.js", "--quiet"])));
+    let cmd = "@echo off\r\n\"C:\\tools\\cli.exe\" --quiet %*\r\n";
+    assert_eq!(binclude_command(&Interpreter::Cmd, cmd), Ok(words(&["C:\\tools\\cli.exe", "--quiet"])));
+    assert_eq!(binclude_command(&Interpreter::Bash, "echo nothing forwarded\n"), Err("command not found"));
+    assert_eq!(binclude_command(&Interpreter::Python, "print()\n"), Err("unsupported interpreter"));
+  }
+
+  #[test]
+  fn binclude_directories_are_imported_by_file_name() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("tool"), "#!/bin/bash\ntool-bin --flag \"$@\"\n").unwrap();
+    fs::write(dir.path().join("other.cmd"), "@echo off\nother.exe %*\n").unwrap();
+    fs::write(dir.path().join("notes.txt"), "").unwrap();
+    let imported = import(&ImportSource::Binclude, dir.path(), &None).unwrap();
+    assert_eq!(commands(&imported), vec![
+      ("other".to_string(), words(&["other.exe"])),
+      ("tool".to_string(), words(&["tool-bin", "--flag"]))
+    ]);
+    assert_eq!(imported.rejected.len(), 1);
+    assert_eq!(imported.rejected[0].reason, "unknown extension");
+  }
+}
@@ -1,8 +1,10 @@
 use serde::{Serialize, Deserialize};
+use std::path;
 
-use crate::errors::{ScuError, self};
+use crate::{errors::{ScuError, self}, suggestions};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Language the scripts of a shortcut are generated for
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Interpreter {
   Bash,
   Cmd,
@@ -14,6 +16,7 @@ pub enum Interpreter {
 }
 
 impl Interpreter {
+  /// Interpreters targeted by default
   pub fn all() -> [Self; 6] {
     [
       Self::Bash,
@@ -25,6 +28,7 @@ impl Interpreter {
     ]
   }
 
+  /// Interpreter by case insensitive name
   pub fn from_name(name: impl AsRef<str>) -> Option<Self> {
     match name.as_ref().to_ascii_lowercase().as_str() {
       "bash" => Some(Self::Bash),
@@ -38,6 +42,7 @@ impl Interpreter {
     }
   }
 
+  /// Interpreter by file extension, including the dot
   pub fn from_extension(extension: impl AsRef<str>) -> Option<Self> {
     match extension.as_ref().to_ascii_lowercase().as_str() {
       ".sh" => Some(Self::Bash),
@@ -51,6 +56,12 @@ impl Interpreter {
     }
   }
 
+  /// Whether its scripts apply the timeout and retries of the shortcut, the others run the command once
+  pub fn applies_limits(&self) -> bool {
+    matches!(self, Self::Bash | Self::Python | Self::Pythonw)
+  }
+
+  /// Lowercase name, as accepted by [`Interpreter::from_name`]
   pub fn name(&self) -> &'static str {
     match self {
       Self::Bash => "bash",
@@ -63,6 +74,7 @@ impl Interpreter {
     }
   }
 
+  /// File extension of the scripts, including the dot
   pub fn extension(&self) -> &'static str {
     match self {
       Self::Bash => ".sh",
@@ -75,21 +87,34 @@ impl Interpreter {
     }
   }
 
+  /// Extension used for generated scripts, empty for interpreters invoked by shebang
   pub fn preferred_extension(&self) -> &'static str {
     if self.prefer_no_extension() { "" } else { self.extension() }
   }
 
+  /// Command running a script with the interpreter, the script can be run this way whatever its extension
+  pub fn invocation(&self, script: impl AsRef<path::Path>) -> Vec<String> {
+    let launcher: &[&str] = match self {
+      Self::Bash => &["bash"],
+      Self::Cmd | Self::Batch => &["cmd", "/C"],
+      Self::Powershell if cfg!(windows) => &["powershell", "-NoProfile", "-ExecutionPolicy", "Bypass", "-File"],
+      Self::Powershell => &["pwsh", "-NoProfile", "-File"],
+      Self::Python => &["python"],
+      Self::Pythonw => &["pythonw"],
+      Self::Ruby => &["ruby"],
+    };
+    launcher.iter().map(|x| x.to_string()).chain([script.as_ref().to_string_lossy().to_string()]).collect()
+  }
+
   pub fn prefer_no_extension(&self) -> bool {
     matches!(self, Self::Bash)
   }
 
+  /// Parses a list of names, failing with suggestions on the first unknown
   pub fn try_collect(interpreters: Option<&[impl AsRef<str>]>) -> errors::Result<Option<Vec<Interpreter>>> {
-    match interpreters.and_then(
-      |x| Some(x.iter().map(|x| x.as_ref().try_into()).collect::<errors::Result<Vec<Interpreter>>>())
-    ) {
-      Some(result) => result.map(|ok| Some(ok)),
-      None => Ok(None),
-    }
+    interpreters.map(
+      |x| x.iter().map(|x| x.as_ref().try_into()).collect::<errors::Result<Vec<Interpreter>>>()
+    ).transpose()
   }
 }
 
@@ -97,6 +122,9 @@ impl TryFrom<&str> for Interpreter {
   type Error = ScuError;
 
   fn try_from(value: &str) -> Result<Self, Self::Error> {
-    Self::from_name(value).ok_or(ScuError::StringError(format!("Interpreter not registered: {}", value)))
+    Self::from_name(value).ok_or_else(|| ScuError::UnknownInterpreter {
+      name: value.to_string(),
+      suggestions: suggestions::closest(value, Self::all().iter().chain([&Self::Batch]).map(|x| x.name()))
+    })
   }
 }
\ No newline at end of file
@@ -0,0 +1,54 @@
+//! Shortcut templates that can be made into scripts for multiple interpreters.
+//!
+//! The [`Controller`] owns the `scu_data` directory: it stores the templates, makes the scripts in `bin` and keeps
+//! the history of changes. Nothing is printed by the library, operations return their results and the warnings
+//! produced along the way can be taken with [`Controller::take_warnings`].
+//!
+//! ```no_run
+//! use scu::{Controller, Shortcut, Interpreter};
+//!
+//! let mut controller = Controller::new()?;
+//! controller.setup()?;
+//! let shortcut = Shortcut::builder()
+//!   .name("greet")
+//!   .interpreters(Some(vec![Interpreter::Bash]))
+//!   .command(vec!["echo".into(), "hello".into()])
+//!   .build();
+//! // Checks the names, stores the template, records it in the history and makes its scripts
+//! controller.save("set", shortcut, false, false, true)?;
+//! # Ok::<(), scu::ScuError>(())
+//! ```
+
+pub mod paths;
+pub mod errors;
+pub mod script;
+pub mod streams;
+pub mod resource;
+pub mod execution;
+pub mod reader;
+pub mod editor;
+pub mod importer;
+pub mod bundle;
+pub mod doctor;
+pub mod profile;
+mod suggestions;
+pub mod lock;
+pub mod history;
+pub mod log;
+pub mod migration;
+pub mod store;
+pub mod config;
+pub mod sync;
+pub mod startup;
+pub mod shortcut;
+pub mod controller;
+pub mod interpreter;
+
+pub use controller::{Controller, Listing};
+pub use shortcut::{Shortcut, ShortcutBuilder, ShortcutBody, ShortcutFile, ShortcutFilter};
+pub use interpreter::Interpreter;
+pub use script::{Script, Shell};
+pub use streams::Streams;
+pub use resource::{Resource, ResourceSource, EmbeddedFile};
+pub use store::{ShortcutStore, StoreKind, DirectoryStore, SingleFileStore, MemoryStore};
+pub use errors::{Result, ScuError};
@@ -0,0 +1,99 @@
+use std::{fs, io::{self, Write}, path, process, thread, time::{Duration, Instant}};
+
+use crate::errors::{Result, ScuError};
+
+const WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+const WAIT_STEP: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub struct Lock {
+  path: path::PathBuf
+}
+
+#[cfg(target_os = "linux")]
+fn is_stale(owner: &str) -> bool {
+  owner.trim().parse::<u32>().map(|pid| !path::Path::new("/proc").join(pid.to_string()).exists()).unwrap_or(false)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_stale(_owner: &str) -> bool {
+  false
+}
+
+impl Lock {
+  fn try_acquire(path: &path::Path) -> io::Result<Option<Self>> {
+    match fs::OpenOptions::new().write(true).create_new(true).open(path) {
+      Ok(mut file) => {
+        write!(file, "{}", process::id())?;
+        Ok(Some(Lock { path: path.to_path_buf() }))
+      },
+      Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+        if fs::read_to_string(path).map(|owner| is_stale(&owner)).unwrap_or(false) {
+          fs::remove_file(path)?;
+          return Self::try_acquire(path)
+        }
+        Ok(None)
+      },
+      Err(err) => Err(err)
+    }
+  }
+
+  pub fn acquire(path: impl AsRef<path::Path>) -> Result<Self> {
+    let start = Instant::now();
+    loop {
+      if let Some(lock) = Self::try_acquire(path.as_ref())? {
+        return Ok(lock)
+      }
+      if start.elapsed() >= WAIT_TIMEOUT {
+        return Err(ScuError::Locked { path: path.as_ref().to_path_buf() })
+      }
+      thread::sleep(WAIT_STEP);
+    }
+  }
+}
+
+impl Drop for Lock {
+  fn drop(&mut self) {
+    let _ = fs::remove_file(&self.path);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  #[test]
+  fn held_lock_fails_after_the_wait() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lock");
+    let _held = Lock::acquire(&path).unwrap();
+    let start = Instant::now();
+    let err = Lock::acquire(&path).unwrap_err();
+    assert!(start.elapsed() >= WAIT_TIMEOUT);
+    assert!(matches!(err, ScuError::Locked { .. }));
+    assert_eq!(err.exit_code(), 11);
+  }
+
+  #[test]
+  fn lock_file_is_removed_on_drop() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lock");
+    let lock = Lock::acquire(&path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), process::id().to_string());
+    drop(lock);
+    assert!(!path.exists());
+  }
+
+  #[cfg(target_os = "linux")]
+  #[test]
+  fn stale_lock_is_taken_over() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("lock");
+    let mut child = process::Command::new("true").spawn().unwrap();
+    child.wait().unwrap();
+    fs::write(&path, child.id().to_string()).unwrap();
+    let _lock = Lock::acquire(&path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), process::id().to_string());
+  }
+}
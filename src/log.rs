@@ -0,0 +1,134 @@
+use serde::{Serialize, Deserialize};
+use std::{fs, io::Write, path, collections::HashMap};
+
+use crate::errors::Result;
+
+/// Execution of a shortcut by `scu run`, retries are part of the same run
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Run {
+  pub timestamp: u64,
+  pub name: String,
+  pub args: Vec<String>,
+  /// Milliseconds from the start of the first attempt to the end of the last one
+  pub duration: u64,
+  /// Exit code of the last attempt, missing when it timed out, was killed by a signal or could not start
+  pub code: Option<i32>
+}
+
+impl Run {
+  pub fn succeeded(&self) -> bool {
+    self.code == Some(0)
+  }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Runs {
+  #[serde(default)]
+  run: Vec<Run>
+}
+
+/// Usage of a shortcut according to the log
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+  pub name: String,
+  pub runs: u32,
+  pub failures: u32,
+  pub last_run: Option<u64>,
+  pub last_failure: Option<u64>
+}
+
+impl Stats {
+  /// Fraction of the runs that failed, 0 when it never ran
+  pub fn failure_rate(&self) -> f32 {
+    if self.runs == 0 { 0.0 } else { self.failures as f32 / self.runs as f32 }
+  }
+
+  /// Whether it did not run since the timestamp
+  pub fn idle_since(&self, timestamp: u64) -> bool {
+    self.last_run.map(|last_run| last_run < timestamp).unwrap_or(true)
+  }
+}
+
+/// File the runs are appended to, each one as a `[[run]]` table so the file stays a valid toml document
+pub struct Log {
+  file: path::PathBuf
+}
+
+impl Log {
+  pub fn new(file: path::PathBuf) -> Self {
+    Log { file }
+  }
+
+  pub fn append(&self, run: &Run) -> Result<()> {
+    let entry = format!("[[run]]\n{}\n", toml::to_string(run)?);
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(&self.file)?;
+    file.write_all(entry.as_bytes()).map_err(|err| err.into())
+  }
+
+  /// Every run in the order they were appended, none when the log does not exist
+  pub fn runs(&self) -> Result<Vec<Run>> {
+    if !self.file.exists() {
+      return Ok(Vec::new())
+    }
+    Ok(toml::from_str::<Runs>(fs::read_to_string(&self.file)?.as_str())?.run)
+  }
+
+  /// Usage of each of the names, including the ones that never ran
+  pub fn stats<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> Result<Vec<Stats>> {
+    let mut stats: HashMap<&str, Stats> = names.into_iter()
+      .map(|name| (name, Stats { name: name.to_string(), ..Stats::default() }))
+      .collect();
+    for run in self.runs()? {
+      if let Some(stats) = stats.get_mut(run.name.as_str()) {
+        stats.runs += 1;
+        stats.last_run = stats.last_run.max(Some(run.timestamp));
+        if !run.succeeded() {
+          stats.failures += 1;
+          stats.last_failure = stats.last_failure.max(Some(run.timestamp));
+        }
+      }
+    }
+    let mut stats: Vec<Stats> = stats.into_values().collect();
+    stats.sort_by(|a, b| b.runs.cmp(&a.runs).then_with(|| a.name.cmp(&b.name)));
+    Ok(stats)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  fn run(name: &str, timestamp: u64, code: Option<i32>) -> Run {
+    Run { timestamp, name: name.to_string(), args: vec!["a b".to_string()], duration: 5, code }
+  }
+
+  #[test]
+  fn appended_runs_are_read_back() {
+    let dir = TempDir::new().unwrap();
+    let log = Log::new(dir.path().join("log"));
+    assert!(log.runs().unwrap().is_empty());
+    log.append(&run("greet", 10, Some(0))).unwrap();
+    log.append(&run("greet", 20, None)).unwrap();
+    assert_eq!(log.runs().unwrap(), vec![run("greet", 10, Some(0)), run("greet", 20, None)]);
+  }
+
+  #[test]
+  fn stats_count_the_runs_of_each_name() {
+    let dir = TempDir::new().unwrap();
+    let log = Log::new(dir.path().join("log"));
+    for entry in [run("a", 10, Some(0)), run("a", 30, Some(1)), run("a", 20, Some(0)), run("b", 15, Some(0)), run("gone", 5, Some(0))] {
+      log.append(&entry).unwrap();
+    }
+    let stats = log.stats(["a", "b", "unused"]).unwrap();
+    assert_eq!(stats, vec![
+      Stats { name: "a".into(), runs: 3, failures: 1, last_run: Some(30), last_failure: Some(30) },
+      Stats { name: "b".into(), runs: 1, failures: 0, last_run: Some(15), last_failure: None },
+      Stats { name: "unused".into(), ..Stats::default() }
+    ]);
+    assert!((stats[0].failure_rate() - 1.0 / 3.0).abs() < f32::EPSILON);
+    assert!(stats[1].idle_since(20));
+    assert!(!stats[0].idle_since(20));
+    assert!(stats[2].idle_since(0));
+  }
+}
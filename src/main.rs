@@ -1,21 +1,34 @@
 mod cli;
-mod paths;
-mod errors;
-mod script;
-mod reader;
-mod startup;
-mod shortcut;
-mod controller;
-mod interpreter;
+mod output;
+
+use std::{env, process};
 
 use clap::Parser;
 
+use scu::{Controller, Result};
+
+/// Overrides the directory containing `scu_data`, which defaults to the executable directory
+const ROOT_VAR: &str = "SCU_ROOT";
+
+fn controller() -> Result<Controller> {
+  let mut controller = match env::var_os(ROOT_VAR) {
+    Some(root) => Controller::from_root(root)?,
+    None => Controller::new()?
+  };
+  controller.setup()?;
+  Ok(controller)
+}
+
 fn main() {
   let args = cli::Cli::parse();
-  let mut controller = controller::Controller::new().unwrap();
-
-  controller.setup().unwrap();
 
-  let result = args.command.apply(&mut controller);
-  controller.handle_result(result);
+  let result = controller().and_then(|mut controller| {
+    let result = args.command.apply(&mut controller);
+    output::flush_warnings(&controller);
+    result
+  });
+  output::handle_result(&result);
+  if let Err(err) = result {
+    process::exit(err.exit_code());
+  }
 }
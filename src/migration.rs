@@ -0,0 +1,179 @@
+use serde::{Serialize, de::Error};
+
+use crate::{shortcut::Shortcut, controller::RES_DIR};
+
+/// Version written to every stored shortcut template
+pub const CURRENT_VERSION: u32 = 2;
+pub const VERSION_KEY: &str = "version";
+
+/// Each migration takes a template from its position in the list to the next version
+type Migration = fn(&mut toml::Table) -> Result<(), toml::de::Error>;
+
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] = [
+  // Templates written before versioning share the current layout, they only need the version stamp
+  |_| Ok(()),
+  // Script resources were kept directly in the resources directory, now each shortcut has its own
+  |table| {
+    let name = table.get("name").and_then(|name| name.as_str()).unwrap_or_default().to_string();
+    let script = table.get_mut("body").and_then(|body| body.get_mut("command")).and_then(|command| command.get_mut("script"));
+    if let Some(toml::Value::String(script)) = script {
+      *script = namespace_resource(script, &name);
+    }
+    Ok(())
+  },
+];
+
+/// Moves a path directly inside the resources directory to the subdirectory of the shortcut, keeping its separators
+fn namespace_resource(path: &str, shortcut: &str) -> String {
+  match path.rfind(['/', '\\']) {
+    Some(end) if path[..end].rsplit(['/', '\\']).next() == Some(RES_DIR) && !shortcut.is_empty() =>
+      format!("{}{}{}{}", &path[..end], &path[end..=end], shortcut, &path[end..]),
+    _ => path.to_string()
+  }
+}
+
+#[derive(Serialize)]
+struct Versioned<'a> {
+  version: u32,
+  #[serde(flatten)]
+  shortcut: &'a Shortcut
+}
+
+fn unsupported(version: impl std::fmt::Display) -> toml::de::Error {
+  toml::de::Error::custom(format!("version {} is newer than the supported version {}, update scu", version, CURRENT_VERSION))
+}
+
+pub fn version(table: &toml::Table) -> Result<u32, toml::de::Error> {
+  match table.get(VERSION_KEY) {
+    None => Ok(0),
+    Some(toml::Value::Integer(version)) if *version >= 0 => u32::try_from(*version).map_err(|_| unsupported(version)),
+    Some(value) => Err(toml::de::Error::custom(format!("invalid version: {}", value)))
+  }
+}
+
+/// Brings the table to the current version, returns the version it had before
+pub fn upgrade(table: &mut toml::Table) -> Result<u32, toml::de::Error> {
+  let version = version(table)?;
+  if version > CURRENT_VERSION {
+    return Err(unsupported(version))
+  }
+  for migration in &MIGRATIONS[version as usize..] {
+    migration(table)?;
+  }
+  table.remove(VERSION_KEY);
+  Ok(version)
+}
+
+/// Parses a stored template of any known version, returns it along with the version it was written in
+pub fn from_str(data: &str) -> Result<(Shortcut, u32), toml::de::Error> {
+  from_table(toml::from_str(data)?)
+}
+
+pub fn from_table(mut table: toml::Table) -> Result<(Shortcut, u32), toml::de::Error> {
+  let version = upgrade(&mut table)?;
+  Ok((toml::Value::Table(table).try_into()?, version))
+}
+
+pub fn to_string(shortcut: &Shortcut) -> Result<String, toml::ser::Error> {
+  toml::to_string_pretty(&Versioned { version: CURRENT_VERSION, shortcut })
+}
+
+pub fn to_table(shortcut: &Shortcut) -> Result<toml::Table, toml::ser::Error> {
+  toml::Table::try_from(Versioned { version: CURRENT_VERSION, shortcut })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::shortcut::ShortcutBody;
+
+  macro_rules! fixtures {
+    ($version:literal: $($name:literal),*) => {
+      [$((concat!("v", $version, "/", $name), include_str!(concat!("../tests/fixtures/shortcuts/v", $version, "/", $name, ".toml")))),*]
+    };
+  }
+
+  fn all_fixtures() -> Vec<(u32, &'static str, &'static str)> {
+    let v0 = fixtures!("0": "command", "command_with_script", "annotated");
+    let v1 = fixtures!("1": "command", "command_with_script");
+    let v2 = fixtures!("2": "command", "command_with_script", "resources");
+    v0.into_iter().map(|(name, data)| (0, name, data))
+      .chain(v1.into_iter().map(|(name, data)| (1, name, data)))
+      .chain(v2.into_iter().map(|(name, data)| (2, name, data)))
+      .collect()
+  }
+
+  #[test]
+  fn every_version_has_fixtures() {
+    for version in 0..=CURRENT_VERSION {
+      assert!(all_fixtures().iter().any(|(fixture, _, _)| *fixture == version), "no fixtures for version {}", version);
+    }
+  }
+
+  #[test]
+  fn fixtures_load_with_their_version() {
+    for (version, name, data) in all_fixtures() {
+      let (_, loaded) = from_str(data).unwrap_or_else(|err| panic!("{}: {}", name, err));
+      assert_eq!(loaded, version, "{}", name);
+    }
+  }
+
+  #[test]
+  fn fixtures_are_stored_in_the_current_version() {
+    for (_, name, data) in all_fixtures() {
+      let (shortcut, _) = from_str(data).unwrap();
+      let stored = to_string(&shortcut).unwrap();
+      assert!(stored.starts_with(&format!("version = {}\n", CURRENT_VERSION)), "{}", name);
+      let (reloaded, version) = from_str(&stored).unwrap();
+      assert_eq!(version, CURRENT_VERSION, "{}", name);
+      assert_eq!(to_string(&reloaded).unwrap(), stored, "{}", name);
+    }
+  }
+
+  #[test]
+  fn migrated_fixtures_keep_their_content() {
+    let (shortcut, _) = from_str(include_str!("../tests/fixtures/shortcuts/v0/command_with_script.toml")).unwrap();
+    assert_eq!(shortcut.name, "report");
+    assert!(shortcut.startup.is_some());
+    match shortcut.body {
+      ShortcutBody::CommandWithScript { cmd, script_offset, body, .. } => {
+        assert_eq!(cmd, vec!["python"]);
+        assert_eq!(script_offset, Some(1));
+        assert_eq!(body, "print('report')\n");
+      },
+      _ => panic!("unexpected body")
+    }
+    let (shortcut, _) = from_str(include_str!("../tests/fixtures/shortcuts/v0/annotated.toml")).unwrap();
+    assert_eq!(shortcut.aliases, Some(vec!["srv".to_string()]));
+    assert!(shortcut.has_tag("web"));
+  }
+
+  #[test]
+  fn script_resources_are_moved_to_the_shortcut_directory() {
+    for (version, name, data) in all_fixtures() {
+      let (shortcut, _) = from_str(data).unwrap();
+      if let ShortcutBody::CommandWithScript { script, .. } = shortcut.body {
+        let expected = if version < 2 { "scu_data\\res\\report\\report.py" } else { "scu_data/res/report/report.py" };
+        assert_eq!(script.to_string_lossy(), expected, "{}", name);
+      }
+    }
+    assert_eq!(namespace_resource("/data/res/run.py", "tool"), "/data/res/tool/run.py");
+    assert_eq!(namespace_resource("/data/res/tool/run.py", "tool"), "/data/res/tool/run.py");
+    assert_eq!(namespace_resource("/elsewhere/run.py", "tool"), "/elsewhere/run.py");
+  }
+
+  #[test]
+  fn newer_versions_are_rejected() {
+    let data = format!("version = {}\nname = \"x\"\n\n[body]\ntype = \"Command\"\ncommand = [\"x\"]\n", CURRENT_VERSION + 1);
+    assert!(from_str(&data).unwrap_err().to_string().contains("newer"));
+  }
+
+  #[test]
+  fn invalid_versions_are_rejected() {
+    assert!(from_str("version = \"one\"\nname = \"x\"\n\n[body]\ntype = \"Command\"\ncommand = [\"x\"]\n").is_err());
+    assert!(from_str("version = -1\nname = \"x\"\n\n[body]\ntype = \"Command\"\ncommand = [\"x\"]\n").is_err());
+    // Would wrap to version 1 if truncated
+    let wrapping = format!("version = {}\nname = \"x\"\n\n[body]\ntype = \"Command\"\ncommand = [\"x\"]\n", (1i64 << 32) + 1);
+    assert!(from_str(&wrapping).unwrap_err().to_string().contains("newer"));
+  }
+}
@@ -0,0 +1,116 @@
+use std::{fmt, borrow::Borrow, error::Error};
+
+use scu::{Controller, Listing, Shortcut, ShortcutFile, ScuError, Result, paths};
+
+pub fn log(data: impl fmt::Display) {
+  println!("{}", data)
+}
+
+pub fn notify_changes(verb: impl fmt::Display, count: i32) {
+  println!("{} {} shortcut{}", verb, count, if count == 1 { "" } else { "s" })
+}
+
+pub fn handle_error(err: impl Borrow<ScuError>) {
+  let mut message = err.borrow().to_string();
+  eprintln!("Error: {}", message);
+  let mut source = err.borrow().source();
+  while let Some(err) = source {
+    // Wrapped errors display the same message as their source
+    let cause = err.to_string();
+    if cause != message {
+      eprintln!(" |> Caused by: {}", cause);
+    }
+    message = cause;
+    source = err.source();
+  }
+}
+
+pub fn handle_result<T>(result: impl Borrow<Result<T>>) {
+  if let Err(err) = result.borrow() {
+    handle_error(err);
+  }
+}
+
+pub fn flush_warnings(controller: &Controller) {
+  for warning in controller.take_warnings() {
+    eprintln!("Warning: {}", warning);
+  }
+}
+
+pub fn operate_many<'a, T>(
+  controller: &mut Controller,
+  items: &'a mut [T],
+  mut action: impl FnMut(&mut Controller, &'a mut T) -> Result<bool>
+) -> i32 {
+  items.iter_mut().map(|item| {
+    let result = action(controller, item);
+    flush_warnings(controller);
+    handle_result(&result);
+    result.unwrap_or(false) as i32
+  }).sum()
+}
+
+pub fn list(listing: &Listing, notify_errors: bool, verbose: bool, columns: bool) {
+  if columns {
+    list_columns(&listing.shortcuts);
+  } else {
+    listing.shortcuts.iter().for_each(|shortcut| list_shortcut(shortcut, verbose));
+  }
+  if notify_errors {
+    for (file_name, err) in &listing.invalid {
+      println!("> Invalid template: {}", file_name);
+      if verbose {
+        println!("'''\n{}'''", err.source().map(|source| source.to_string()).unwrap_or(err.to_string()));
+      }
+    }
+  }
+}
+
+fn list_shortcut(shortcut: &Shortcut, verbose: bool) {
+  match &shortcut.aliases {
+    Some(aliases) => println!("> {} ({}) => {}", shortcut.name, aliases.join(", "), shortcut.body),
+    None => println!("> {} => {}", shortcut.name, shortcut.body)
+  }
+  if verbose {
+    if let Some(description) = &shortcut.description {
+      println!(" |> Description: {}", description);
+    }
+    if let Some(tags) = &shortcut.tags {
+      println!(" |> Tags: {}", tags.join(", "));
+    }
+    if let Some(interpreters) = &shortcut.interpreters {
+      println!(
+        " |> Interpreters: {}",
+        interpreters.iter().map(|i| i.name().to_string())
+          .reduce(|a, b| format!("{}, {}", a, b)).unwrap_or(String::new())
+      )
+    }
+    let resources = shortcut.resource_paths();
+    if !resources.is_empty() {
+      println!(" |> Resources: {}", resources.iter().map(paths::stringify_default).collect::<Vec<String>>().join(", "));
+    }
+    if let Some(startup) = &shortcut.startup {
+      println!(" |> Startup: {}", startup);
+    }
+  }
+}
+
+fn list_columns(shortcuts: &[ShortcutFile]) {
+  let rows: Vec<[String; 4]> = shortcuts.iter().map(|shortcut| [
+    shortcut.name.clone(),
+    shortcut.body.to_string(),
+    shortcut.tags.as_ref().map(|tags| tags.join(",")).unwrap_or_default(),
+    shortcut.description.clone().unwrap_or_default()
+  ]).collect();
+  let header = ["NAME", "COMMAND", "TAGS", "DESCRIPTION"].map(String::from);
+  let mut widths = [0; 4];
+  for row in std::iter::once(&header).chain(rows.iter()) {
+    for (width, cell) in widths.iter_mut().zip(row) {
+      *width = (*width).max(cell.chars().count());
+    }
+  }
+  for row in std::iter::once(&header).chain(rows.iter()) {
+    let line: Vec<String> = row.iter().zip(widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+    println!("{}", line.join("  ").trim_end());
+  }
+}
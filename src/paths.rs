@@ -1,4 +1,4 @@
-use std::{path, borrow::Cow};
+use std::{path, env, fs, io, process, borrow::Cow};
 
 pub fn stringify(path: impl AsRef<path::Path>, separator: impl AsRef<str>) -> String {
   let components = path.as_ref().components().map(|c| match c {
@@ -16,6 +16,107 @@ pub fn stringify(path: impl AsRef<path::Path>, separator: impl AsRef<str>) -> St
   ).map(|x| x.0.to_string()).unwrap_or_default()
 }
 
+/// Separator for paths embedded in generated scripts, backslashes are escaped since they end up inside string literals
+#[cfg(windows)]
+pub const SCRIPT_SEPARATOR: &str = "\\\\";
+#[cfg(not(windows))]
+pub const SCRIPT_SEPARATOR: &str = "/";
+
 pub fn stringify_default(path: impl AsRef<path::Path>) -> String {
   stringify(path, path::MAIN_SEPARATOR.to_string())
-}
\ No newline at end of file
+}
+
+const INVALID_CHARS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+const RESERVED_NAMES: [&str; 22] = [
+  "CON", "PRN", "AUX", "NUL",
+  "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+  "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9"
+];
+
+pub fn invalid_name_reason(name: impl AsRef<str>) -> Option<&'static str> {
+  let name = name.as_ref();
+  if name.is_empty() || name == "." || name == ".." {
+    Some("empty or relative name")
+  } else if name.contains(|c: char| INVALID_CHARS.contains(&c) || c.is_control()) {
+    Some("contains characters not allowed in file names")
+  } else if name.ends_with(['.', ' ']) {
+    Some("ends with a dot or a space")
+  } else if RESERVED_NAMES.contains(&name.split('.').next().unwrap_or_default().to_ascii_uppercase().as_str()) {
+    Some("reserved file name")
+  } else {
+    None
+  }
+}
+
+#[cfg(unix)]
+pub fn is_executable(file: &path::Path) -> bool {
+  use std::os::unix::fs::PermissionsExt;
+  file.metadata().map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0).unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+pub fn is_executable(file: &path::Path) -> bool {
+  file.is_file()
+}
+
+#[cfg(unix)]
+pub fn set_executable(file: &path::Path) -> std::io::Result<()> {
+  use std::os::unix::fs::PermissionsExt;
+  let mut permissions = fs::metadata(file)?.permissions();
+  permissions.set_mode(permissions.mode() | 0o755);
+  fs::set_permissions(file, permissions)
+}
+
+#[cfg(not(unix))]
+pub fn set_executable(_file: &path::Path) -> std::io::Result<()> {
+  Ok(())
+}
+
+/// Octal permissions of the file, like `644`
+#[cfg(unix)]
+pub fn mode(file: &path::Path) -> Option<String> {
+  use std::os::unix::fs::PermissionsExt;
+  file.metadata().ok().map(|m| format!("{:o}", m.permissions().mode() & 0o777))
+}
+
+#[cfg(not(unix))]
+pub fn mode(_file: &path::Path) -> Option<String> {
+  None
+}
+
+fn executable_candidates(name: &str) -> Vec<String> {
+  match env::var("PATHEXT") {
+    Ok(extensions) if cfg!(target_os = "windows") =>
+      extensions.split(';').filter(|x| !x.is_empty()).map(|extension| format!("{}{}", name, extension)).collect(),
+    _ => vec![name.to_string()]
+  }
+}
+
+pub fn in_path(dir: impl AsRef<path::Path>) -> bool {
+  let dir = dir.as_ref().canonicalize().unwrap_or(dir.as_ref().to_path_buf());
+  env::var_os("PATH").map(
+    |var| env::split_paths(&var).any(|entry| entry.canonicalize().map(|entry| entry == dir).unwrap_or(false))
+  ).unwrap_or(false)
+}
+
+pub fn find_executable(name: impl AsRef<str>, exclude: Option<&path::Path>) -> Option<path::PathBuf> {
+  let exclude = exclude.map(|exclude| exclude.canonicalize().unwrap_or(exclude.to_path_buf()));
+  let candidates = executable_candidates(name.as_ref());
+  env::split_paths(&env::var_os("PATH")?)
+    .filter(|dir| dir.canonicalize().map(|dir| Some(dir) != exclude).unwrap_or(false))
+    .flat_map(|dir| candidates.iter().map(move |candidate| dir.join(candidate)))
+    .find(|file| is_executable(file))
+}
+
+pub fn write_atomic(file: impl AsRef<path::Path>, contents: impl AsRef<[u8]>, executable: bool) -> io::Result<()> {
+  let file = file.as_ref();
+  let name = file.file_name().ok_or(io::Error::new(io::ErrorKind::InvalidInput, "Missing file name"))?;
+  let temp = file.with_file_name(format!(".{}.{}.tmp", name.to_string_lossy(), process::id()));
+  let result = fs::write(&temp, contents)
+    .and_then(|_| if executable { set_executable(&temp) } else { Ok(()) })
+    .and_then(|_| fs::rename(&temp, file));
+  if result.is_err() {
+    let _ = fs::remove_file(&temp);
+  }
+  result
+}
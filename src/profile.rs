@@ -0,0 +1,137 @@
+use std::{env, fs, path};
+
+use home::home_dir;
+
+use crate::{errors::{Result, ScuError}, paths, reader, script::escape};
+
+const BLOCK_START: &str = "# >>> scu >>>";
+const BLOCK_END: &str = "# <<< scu <<<";
+
+#[derive(Debug)]
+pub struct ProfileFile {
+  path: path::PathBuf,
+  fish: bool
+}
+
+impl ProfileFile {
+  pub fn detect() -> Result<Self> {
+    let home = home_dir().ok_or(ScuError::HomeNotFound)?;
+    let shell = env::var("SHELL").unwrap_or_default();
+    let (file, fish) = match path::Path::new(&shell).file_name().and_then(|x| x.to_str()) {
+      Some("bash") => (home.join(".bashrc"), false),
+      Some("zsh") => (home.join(".zshrc"), false),
+      Some("fish") => (home.join(".config").join("fish").join("conf.d").join("scu.fish"), true),
+      _ => (home.join(".profile"), false)
+    };
+    Ok(ProfileFile { path: file, fish })
+  }
+
+  pub fn path(&self) -> &path::Path {
+    &self.path
+  }
+
+  fn read(&self) -> Result<String> {
+    if self.path.exists() { reader::from_file(&self.path) } else { Ok(String::new()) }
+  }
+
+  fn block(&self, bin: &path::Path) -> String {
+    let bin = paths::stringify_default(bin);
+    let line = if self.fish {
+      format!("set -gx PATH \"{}\" $PATH", escape(&bin, &['\\', '"', '$']))
+    } else {
+      format!("export PATH=\"{}:$PATH\"", escape(&bin, &['\\', '"', '$', '`']))
+    };
+    format!("{}\n{}\n{}\n", BLOCK_START, line, BLOCK_END)
+  }
+
+  /// Replaces the profile atomically, following it when it is a link to keep the link in place
+  fn write(&self, content: String) -> Result<()> {
+    let file = fs::canonicalize(&self.path).unwrap_or_else(|_| self.path.clone());
+    paths::write_atomic(file, content, false).map_err(|err| err.into())
+  }
+
+  pub fn is_installed(&self) -> Result<bool> {
+    Ok(self.read()?.lines().any(|line| line == BLOCK_START))
+  }
+
+  pub fn install(&self, bin: impl AsRef<path::Path>) -> Result<bool> {
+    let content = self.read()?;
+    let block = self.block(bin.as_ref());
+    if content.contains(&block) {
+      return Ok(false)
+    }
+    let mut content = strip_block(&content);
+    if !content.is_empty() && !content.ends_with('\n') {
+      content.push('\n');
+    }
+    content.push_str(&block);
+    if let Some(parent) = self.path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    self.write(content)?;
+    Ok(true)
+  }
+
+  pub fn uninstall(&self) -> Result<bool> {
+    if !self.is_installed()? {
+      return Ok(false)
+    }
+    let content = strip_block(&self.read()?);
+    if self.fish && content.trim().is_empty() {
+      fs::remove_file(&self.path)?;
+    } else {
+      self.write(content)?;
+    }
+    Ok(true)
+  }
+}
+
+fn strip_block(content: &str) -> String {
+  let mut inside = false;
+  content.lines().filter(|line| match *line {
+    BLOCK_START => { inside = true; false },
+    BLOCK_END if inside => { inside = false; false },
+    _ => !inside
+  }).map(|line| format!("{}\n", line)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  #[test]
+  fn the_bin_path_is_escaped() {
+    let profile = ProfileFile { path: ".bashrc".into(), fish: false };
+    let block = profile.block(path::Path::new("/home/a \"b\"/$HOME/`id`/bin"));
+    assert!(block.contains("export PATH=\"/home/a \\\"b\\\"/\\$HOME/\\`id\\`/bin:$PATH\"\n"));
+    let profile = ProfileFile { path: "scu.fish".into(), fish: true };
+    assert!(profile.block(path::Path::new("/opt/$x/bin")).contains("set -gx PATH \"/opt/\\$x/bin\" $PATH\n"));
+  }
+
+  #[test]
+  fn install_and_uninstall_keep_the_rest_of_the_profile() {
+    let dir = TempDir::new().unwrap();
+    let profile = ProfileFile { path: dir.path().join(".bashrc"), fish: false };
+    fs::write(profile.path(), "alias ll='ls -l'").unwrap();
+    assert!(profile.install("/opt/scu/bin").unwrap());
+    assert!(!profile.install("/opt/scu/bin").unwrap());
+    assert!(profile.is_installed().unwrap());
+    assert!(profile.uninstall().unwrap());
+    assert_eq!(fs::read_to_string(profile.path()).unwrap(), "alias ll='ls -l'\n");
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn linked_profiles_stay_links() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("dotfiles-bashrc");
+    fs::write(&target, "").unwrap();
+    let link = dir.path().join(".bashrc");
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+    let profile = ProfileFile { path: link.clone(), fish: false };
+    profile.install("/opt/scu/bin").unwrap();
+    assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+    assert!(fs::read_to_string(&target).unwrap().contains("/opt/scu/bin"));
+  }
+}
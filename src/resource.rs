@@ -0,0 +1,257 @@
+use serde::{Serialize, Deserialize};
+use std::{collections::BTreeMap, fs, io, path};
+
+use crate::{errors::{Result, ScuError}, paths};
+
+/// What a resource is made from
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceSource {
+  /// Content embedded in the template
+  Content(String),
+  /// Directory copied when the shortcut is made, it must still exist then
+  Directory(path::PathBuf),
+  /// Files of a directory embedded by their path relative to it, `/` separated, bundles carry directories this way
+  Files(BTreeMap<String, EmbeddedFile>)
+}
+
+/// File of an embedded directory
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddedFile {
+  pub content: String,
+  /// Octal permissions, the mode of the resource takes precedence
+  pub mode: Option<String>
+}
+
+/// File or directory written along the scripts of a shortcut, the arguments reference its path with `{res:<name>}`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Resource {
+  pub name: String,
+  pub path: path::PathBuf,
+  #[serde(flatten)]
+  pub source: ResourceSource,
+  /// Octal permissions of the written files, like `755`, ignored outside unix
+  pub mode: Option<String>
+}
+
+impl Resource {
+  /// Placeholder replaced by the path of the resource in the command
+  pub fn placeholder(&self) -> String {
+    format!("{{res:{}}}", self.name)
+  }
+
+  /// Replaces the placeholders of the resources in the argument
+  pub fn expand(arg: &str, resources: &[Resource]) -> String {
+    resources.iter().fold(arg.to_string(), |arg, resource| {
+      arg.replace(&resource.placeholder(), &paths::stringify(&resource.path, paths::SCRIPT_SEPARATOR))
+    })
+  }
+
+  /// Permissions parsed from the octal mode
+  pub fn permissions(&self) -> Result<Option<u32>> {
+    parse_mode(&self.name, self.mode.as_ref())
+  }
+
+  /// Why the relative path of an embedded file would be written outside the resource, if it would
+  pub fn invalid_file_reason(file: &str) -> Option<&'static str> {
+    file.split('/').find_map(paths::invalid_name_reason)
+  }
+
+  /// The resource with the files of a copied directory embedded, so the template works on other machines
+  pub fn embedded(&self) -> Result<Resource> {
+    match &self.source {
+      ResourceSource::Directory(source) => {
+        let mut files = BTreeMap::new();
+        embed_dir(source, "", &mut files)?;
+        Ok(Resource { source: ResourceSource::Files(files), ..self.clone() })
+      },
+      _ => Ok(self.clone())
+    }
+  }
+
+  /// Writes the resource to its path, replacing what was there
+  pub fn write(&self) -> Result<()> {
+    let permissions = self.permissions()?;
+    let error = |err: io::Error| ScuError::ResourceError { path: self.path.clone(), source: Some(err) };
+    if let Some(parent) = self.path.parent() {
+      fs::create_dir_all(parent).map_err(error)?;
+    }
+    match &self.source {
+      ResourceSource::Content(content) => paths::write_atomic(&self.path, content, false)
+        .and_then(|_| set_mode(&self.path, permissions))
+        .map_err(error),
+      ResourceSource::Directory(source) => {
+        if self.path.exists() {
+          fs::remove_dir_all(&self.path).map_err(error)?;
+        }
+        copy_dir(source, &self.path, permissions)
+          .map_err(|err| ScuError::ResourceError { path: source.clone(), source: Some(err) })
+      },
+      ResourceSource::Files(files) => {
+        if self.path.exists() {
+          fs::remove_dir_all(&self.path).map_err(error)?;
+        }
+        fs::create_dir_all(&self.path).map_err(error)?;
+        for (file, embedded) in files {
+          if let Some(reason) = Self::invalid_file_reason(file) {
+            return Err(ScuError::name_error(file, format!("file of resource {}: {}", self.name, reason)))
+          }
+          let permissions = permissions.map_or_else(|| parse_mode(&self.name, embedded.mode.as_ref()), |mode| Ok(Some(mode)))?;
+          let target = file.split('/').fold(self.path.clone(), |path, part| path.join(part));
+          target.parent().map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| paths::write_atomic(&target, &embedded.content, false))
+            .and_then(|_| set_mode(&target, permissions))
+            .map_err(|err| ScuError::ResourceError { path: target.clone(), source: Some(err) })?;
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+fn parse_mode(resource: &str, mode: Option<&String>) -> Result<Option<u32>> {
+  mode.map(|mode| u32::from_str_radix(mode, 8).map_err(
+    |_| ScuError::InvalidMode { resource: resource.to_string(), mode: mode.clone() }
+  )).transpose()
+}
+
+fn error(path: &path::Path) -> impl Fn(io::Error) -> ScuError + '_ {
+  move |err| ScuError::ResourceError { path: path.to_path_buf(), source: Some(err) }
+}
+
+/// Embeds the files of the directory, only text can be embedded so the first binary file fails naming it
+fn embed_dir(source: &path::Path, prefix: &str, files: &mut BTreeMap<String, EmbeddedFile>) -> Result<()> {
+  for entry in fs::read_dir(source).map_err(error(source))? {
+    let entry = entry.map_err(error(source))?;
+    let path = entry.path();
+    let file = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+    if entry.file_type().map_err(error(&path))?.is_dir() {
+      embed_dir(&path, &format!("{}/", file), files)?;
+    } else {
+      let content = String::from_utf8(fs::read(&path).map_err(error(&path))?).map_err(|_| error(&path)(
+        io::Error::new(io::ErrorKind::InvalidData, "binary files can not be embedded, only text")
+      ))?;
+      files.insert(file, EmbeddedFile { content, mode: paths::mode(&path) });
+    }
+  }
+  Ok(())
+}
+
+fn copy_dir(source: &path::Path, target: &path::Path, permissions: Option<u32>) -> io::Result<()> {
+  fs::create_dir_all(target)?;
+  for entry in fs::read_dir(source)? {
+    let entry = entry?;
+    let target = target.join(entry.file_name());
+    if entry.file_type()?.is_dir() {
+      copy_dir(&entry.path(), &target, permissions)?;
+    } else {
+      fs::copy(entry.path(), &target)?;
+      set_mode(&target, permissions)?;
+    }
+  }
+  Ok(())
+}
+
+#[cfg(unix)]
+fn set_mode(file: &path::Path, permissions: Option<u32>) -> io::Result<()> {
+  use std::os::unix::fs::PermissionsExt;
+  match permissions {
+    Some(mode) => fs::set_permissions(file, fs::Permissions::from_mode(mode)),
+    None => Ok(())
+  }
+}
+
+#[cfg(not(unix))]
+fn set_mode(_file: &path::Path, _permissions: Option<u32>) -> io::Result<()> {
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  fn resource(name: &str, path: path::PathBuf, source: ResourceSource, mode: Option<&str>) -> Resource {
+    Resource { name: name.to_string(), path, source, mode: mode.map(String::from) }
+  }
+
+  #[test]
+  fn placeholders_are_replaced_by_the_paths() {
+    let resources = [
+      resource("config.toml", "/res/tool/config.toml".into(), ResourceSource::Content(String::new()), None),
+      resource("lib", "/res/tool/lib".into(), ResourceSource::Directory("/src/lib".into()), None)
+    ];
+    assert_eq!(Resource::expand("--config={res:config.toml}", &resources), "--config=/res/tool/config.toml");
+    assert_eq!(Resource::expand("{res:lib}:{res:lib}", &resources), "/res/tool/lib:/res/tool/lib");
+    assert_eq!(Resource::expand("{res:other}", &resources), "{res:other}");
+  }
+
+  #[test]
+  fn content_is_written_with_its_mode() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("tool").join("run.sh");
+    resource("run.sh", path.clone(), ResourceSource::Content("echo hi\n".into()), Some("755")).write().unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "echo hi\n");
+    assert!(paths::is_executable(&path));
+    let invalid = resource("run.sh", path, ResourceSource::Content(String::new()), Some("rwx"));
+    assert!(invalid.write().is_err());
+  }
+
+  #[test]
+  fn directories_are_copied_replacing_the_previous_copy() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().join("helpers");
+    fs::create_dir_all(source.join("nested")).unwrap();
+    fs::write(source.join("a.py"), "a").unwrap();
+    fs::write(source.join("nested").join("b.py"), "b").unwrap();
+    let target = dir.path().join("res").join("tool").join("helpers");
+    fs::create_dir_all(&target).unwrap();
+    fs::write(target.join("stale.py"), "").unwrap();
+    resource("helpers", target.clone(), ResourceSource::Directory(source), None).write().unwrap();
+    assert_eq!(fs::read_to_string(target.join("a.py")).unwrap(), "a");
+    assert_eq!(fs::read_to_string(target.join("nested").join("b.py")).unwrap(), "b");
+    assert!(!target.join("stale.py").exists());
+  }
+
+  #[test]
+  fn embedded_directories_are_written_like_the_copies() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().join("helpers");
+    fs::create_dir_all(source.join("nested")).unwrap();
+    fs::write(source.join("a.py"), "a").unwrap();
+    fs::write(source.join("nested").join("b.py"), "b").unwrap();
+    let target = dir.path().join("res").join("tool").join("helpers");
+    let embedded = resource("helpers", target.clone(), ResourceSource::Directory(source.clone()), None).embedded().unwrap();
+    fs::remove_dir_all(&source).unwrap();
+    match &embedded.source {
+      ResourceSource::Files(files) => assert_eq!(files.keys().collect::<Vec<_>>(), ["a.py", "nested/b.py"]),
+      source => panic!("not embedded: {:?}", source)
+    }
+    embedded.write().unwrap();
+    assert_eq!(fs::read_to_string(target.join("a.py")).unwrap(), "a");
+    assert_eq!(fs::read_to_string(target.join("nested").join("b.py")).unwrap(), "b");
+  }
+
+  #[test]
+  fn binary_files_can_not_be_embedded() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().join("assets");
+    fs::create_dir_all(&source).unwrap();
+    fs::write(source.join("logo.png"), [0x89, 0x50, 0x4e, 0x47, 0xff, 0xfe]).unwrap();
+    let err = resource("assets", dir.path().join("res"), ResourceSource::Directory(source.clone()), None).embedded().unwrap_err();
+    assert!(err.to_string().contains("logo.png"), "{}", err);
+    assert!(std::error::Error::source(&err).unwrap().to_string().contains("binary files"));
+  }
+
+  #[test]
+  fn embedded_files_stay_inside_the_resource() {
+    let dir = TempDir::new().unwrap();
+    let file = EmbeddedFile { content: "pwned".into(), mode: None };
+    let files = BTreeMap::from([("../escaped".to_string(), file)]);
+    let target = dir.path().join("res").join("tool").join("helpers");
+    assert!(resource("helpers", target, ResourceSource::Files(files), None).write().is_err());
+    assert!(!dir.path().join("res").join("tool").join("escaped").exists());
+    assert_eq!(Resource::invalid_file_reason("nested/b.py"), None);
+    assert!(Resource::invalid_file_reason("nested//b.py").is_some());
+  }
+}
@@ -1,20 +1,166 @@
-use std::fmt::Display;
+use std::{fmt::{self, Display}, path};
 
-use crate::{interpreter::Interpreter, errors::{Result, ScuError}};
+use clap::ValueEnum;
 
+use crate::{interpreter::Interpreter, errors::{Result, ScuError}, streams::Streams, execution::{Execution, BACKOFF}};
+
+/// Script calling a command from an interpreter, displayed as its source
 #[derive(Clone, Debug)]
 pub struct Script<'a> {
   interpreter: &'a Interpreter,
   binary: String,
-  args: Vec<String>
+  args: Vec<String>,
+  streams: Streams,
+  execution: Execution
 }
 
 impl<'a> Script<'a> {
+  /// Fails when the command is empty
   pub fn new(interpreter: &'a Interpreter, command: Vec<String>) -> Result<Self> {
-    command.get(0).ok_or(ScuError::StringError("Expecting at least one element".into())).map(
+    command.first().ok_or(ScuError::EmptyCommand).map(
       |binary| Script {
         interpreter,
         binary: binary.clone(),
+        args: command[1..].to_vec(),
+        streams: Streams::default(),
+        execution: Execution::default()
+      }
+    )
+  }
+
+  /// Redirects the streams of the command from the script
+  pub fn streams(mut self, streams: Streams) -> Self {
+    self.streams = streams;
+    self
+  }
+
+  /// Bounds the command from the script, only bash and python scripts support it
+  pub fn execution(mut self, execution: Execution) -> Self {
+    self.execution = execution;
+    self
+  }
+
+  fn redirections(&self) -> String {
+    let streams = &self.streams;
+    let file = |file: &Option<path::PathBuf>| file.as_ref().map(|file| file.to_string_lossy().to_string());
+    let (stdin, stdout, stderr) = (file(&streams.stdin), file(&streams.stdout), file(&streams.stderr));
+    let quiet = |discard: &str| if streams.quiet { discard.to_string() } else { String::new() };
+    let (operator, mode) = if streams.append { (">>", "a") } else { (">", "w") };
+    match self.interpreter {
+      Interpreter::Bash => {
+        let quote = |file: String| format!("\"{}\"", escape(&file, &['\\', '"', '$', '`']));
+        [
+          stdin.map(|file| format!(" < {}", quote(file))).unwrap_or_default(),
+          stdout.map(|file| format!(" {} {}", operator, quote(file))).unwrap_or_else(|| quiet(" > /dev/null")),
+          stderr.map(|file| format!(" 2{} {}", operator, quote(file))).unwrap_or_else(|| quiet(" 2> /dev/null"))
+        ].concat()
+      },
+      Interpreter::Cmd | Interpreter::Batch => [
+        stdin.map(|file| format!(" < {}", quote_cmd(&file))).unwrap_or_default(),
+        stdout.map(|file| format!(" {} {}", operator, quote_cmd(&file))).unwrap_or_else(|| quiet(" > NUL")),
+        stderr.map(|file| format!(" 2{} {}", operator, quote_cmd(&file))).unwrap_or_else(|| quiet(" 2> NUL"))
+      ].concat(),
+      // Start-Process can not append to the files nor discard the output, hiding the window is the closest to quiet
+      Interpreter::Powershell => {
+        let quote = |file: String| format!("'{}'", file.replace('\'', "''"));
+        [
+          stdin.map(|file| format!("\n$options.RedirectStandardInput = {}", quote(file))).unwrap_or_default(),
+          stdout.map(|file| format!("\n$options.RedirectStandardOutput = {}", quote(file))).unwrap_or_default(),
+          stderr.map(|file| format!("\n$options.RedirectStandardError = {}", quote(file))).unwrap_or_default(),
+          quiet("\n$options.WindowStyle = 'Hidden'")
+        ].concat()
+      },
+      Interpreter::Python | Interpreter::Pythonw => {
+        let quote = |file: String| format!("\"{}\"", escape(&file, &['\\', '"']));
+        [
+          stdin.map(|file| format!(", stdin=open({})", quote(file))).unwrap_or_default(),
+          stdout.map(|file| format!(", stdout=open({}, \"{}\")", quote(file), mode)).unwrap_or_else(|| quiet(", stdout=DEVNULL")),
+          stderr.map(|file| format!(", stderr=open({}, \"{}\")", quote(file), mode)).unwrap_or_else(|| quiet(", stderr=DEVNULL"))
+        ].concat()
+      },
+      Interpreter::Ruby => {
+        let quote = |file: String| format!("\"{}\"", escape(&file, &['\\', '"', '#']));
+        [
+          stdin.map(|file| format!(", in: {}", quote(file))).unwrap_or_default(),
+          stdout.map(|file| format!(", out: [{}, \"{}\"]", quote(file), mode)).unwrap_or_else(|| quiet(", out: File::NULL")),
+          stderr.map(|file| format!(", err: [{}, \"{}\"]", quote(file), mode)).unwrap_or_else(|| quiet(", err: File::NULL"))
+        ].concat()
+      }
+    }
+  }
+}
+
+pub(crate) fn escape(value: &str, special: &[char]) -> String {
+  escape_with(value, special, '\\')
+}
+
+fn escape_with(value: &str, special: &[char], escape: char) -> String {
+  value.chars().fold(String::new(), |mut escaped, c| {
+    if special.contains(&c) {
+      escaped.push(escape);
+    }
+    escaped.push(c);
+    escaped
+  })
+}
+
+/// Double quoted word of bash and zsh, taken literally
+fn quote_posix(arg: &str) -> String {
+  format!("\"{}\"", escape(arg, &['\\', '"', '$', '`']))
+}
+
+fn quote_fish(arg: &str) -> String {
+  format!("\"{}\"", escape(arg, &['\\', '"', '$']))
+}
+
+fn quote_powershell(arg: &str) -> String {
+  format!("\"{}\"", escape_with(arg, &['`', '"', '$'], '`'))
+}
+
+fn quote_python(arg: &str) -> String {
+  format!("\"{}\"", escape(arg, &['\\', '"']).replace('\n', "\\n").replace('\r', "\\r"))
+}
+
+/// Word of a batch file, quoted only when needed since programs parse their own command line
+fn quote_cmd(arg: &str) -> String {
+  let arg = arg.replace('%', "%%");
+  if arg.contains([' ', '"', '&', '|', '<', '>', '^', ',', ';', '=', '(', ')']) {
+    format!("\"{}\"", arg.replace('"', "\"\""))
+  } else {
+    arg
+  }
+}
+
+/// Word of the shell command given to `system`, inside a ruby string
+fn quote_ruby(arg: &str) -> String {
+  escape(&quote_posix(arg), &['\\', '"', '#'])
+}
+
+/// Shell a shortcut can be exported to as a function definition
+#[derive(Clone, Debug, ValueEnum)]
+pub enum Shell {
+  Bash,
+  Zsh,
+  Fish,
+  PowershellProfile,
+}
+
+/// Function definition calling a command from a shell, displayed as its source
+#[derive(Clone, Debug)]
+pub struct Definition<'a> {
+  shell: &'a Shell,
+  name: String,
+  binary: String,
+  args: Vec<String>
+}
+
+impl<'a> Definition<'a> {
+  pub fn new(shell: &'a Shell, name: impl AsRef<str>, command: Vec<String>) -> Result<Self> {
+    command.first().ok_or(ScuError::EmptyCommand).map(
+      |binary| Definition {
+        shell,
+        name: name.as_ref().to_string(),
+        binary: binary.clone(),
         args: command[1..].to_vec()
       }
     )
@@ -23,18 +169,18 @@ impl<'a> Script<'a> {
 
 macro_rules! script_display {
   {$($($variant:ident)* => $template:literal [$($options:tt)*])*} => {
-    impl Display for Script<'_> {
-      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    impl Script<'_> {
+      fn template(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.interpreter {
           $(
             $(Interpreter::$variant)|* => write!(
               f,
               $template,
               script_options_display!([&self.binary], $($options)*),
-              script_options_display!(self.args, $($options)*)
+              script_options_display!(self.args, $($options)*),
+              self.redirections()
             ),
           )*
-          _ => todo!()
         }
       }
     }    
@@ -42,50 +188,160 @@ macro_rules! script_display {
 }
 
 macro_rules! script_options_display {
-  ($source:expr, sep $separator:literal wrap-spaces $wrapper:literal) =>
-    ($source.iter().map(
-      |x| if x.contains(" ") { format!($wrapper, x) } else { x.to_string() }
-    ).collect::<Vec<String>>().join($separator));
-  ($source:expr, sep $separator:literal wrap $wrapper:literal) =>
-    ($source.iter().map(
-      |x| format!($wrapper, x)
-    ).collect::<Vec<String>>().join($separator));
+  ($source:expr, sep $separator:literal quote $quote:ident) =>
+    ($source.iter().map(|x| $quote(x)).collect::<Vec<String>>().join($separator));
+  // Rendered instead of nothing where an empty list would not parse
+  ($source:expr, sep $separator:literal quote $quote:ident empty $empty:literal) =>
+    (if $source.is_empty() { $empty.to_string() } else { script_options_display!($source, sep $separator quote $quote) });
+}
+
+impl Script<'_> {
+  /// Command of a bash script run in a loop, `timeout` kills the group of the command and exits with 124
+  fn bash_retried(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let attempts = self.execution.attempts();
+    let timeout = self.execution.timeout.map(|timeout| format!("timeout {} ", timeout.as_secs_f32())).unwrap_or_default();
+    writeln!(f, "#!/usr/bin/env bash")?;
+    writeln!(f, "for attempt in $(seq {}); do", attempts)?;
+    writeln!(f, "  if [ $attempt -gt 1 ]; then sleep $(( {} << (attempt - 2) )); fi", BACKOFF.as_secs())?;
+    writeln!(
+      f, "  {}{} {} \"$@\"{} && exit 0",
+      timeout, script_options_display!([&self.binary], sep " " quote quote_posix),
+      script_options_display!(self.args, sep " " quote quote_posix), self.redirections()
+    )?;
+    writeln!(f, "  code=$?")?;
+    writeln!(f, "  echo \"Attempt $attempt of {} failed with code $code\" >&2", attempts)?;
+    writeln!(f, "done")?;
+    write!(f, "exit $code")
+  }
+
+  /// Command of a python script run in a loop, on timeout the session of the command is killed like
+  /// `scu run` kills its process group, an interrupt is forwarded to the session before exiting
+  fn python_retried(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let attempts = self.execution.attempts();
+    writeln!(f, "#!/usr/bin/env {}", self.interpreter.name())?;
+    if self.execution.timeout.is_some() {
+      writeln!(f, "import os, signal")?;
+      writeln!(f, "from subprocess import Popen, DEVNULL, TimeoutExpired")?;
+    } else {
+      writeln!(f, "from subprocess import Popen, DEVNULL")?;
+    }
+    writeln!(f, "from sys import argv, stderr")?;
+    writeln!(f, "from time import sleep")?;
+    writeln!(f)?;
+    writeln!(
+      f, "program = [{}, {}]",
+      script_options_display!([&self.binary], sep ", " quote quote_python), script_options_display!(self.args, sep ", " quote quote_python)
+    )?;
+    writeln!(f)?;
+    if self.execution.timeout.is_some() {
+      writeln!(f)?;
+      writeln!(f, "def stop(process, name):")?;
+      writeln!(f, "    if os.name == \"posix\":")?;
+      writeln!(f, "        os.killpg(process.pid, getattr(signal, name))")?;
+      writeln!(f, "    else:")?;
+      writeln!(f, "        process.kill()")?;
+      writeln!(f, "    process.wait()")?;
+      writeln!(f)?;
+      writeln!(f)?;
+    }
+    writeln!(f, "for attempt in range(1, {}):", attempts + 1)?;
+    writeln!(f, "    if attempt > 1:")?;
+    writeln!(f, "        sleep({} * 2 ** (attempt - 2))", BACKOFF.as_secs())?;
+    match self.execution.timeout {
+      Some(timeout) => {
+        writeln!(f, "    process = Popen(program + argv[1:]{}, start_new_session=True)", self.redirections())?;
+        writeln!(f, "    try:")?;
+        writeln!(f, "        code = process.wait(timeout={})", timeout.as_secs_f32())?;
+        writeln!(f, "    except TimeoutExpired:")?;
+        writeln!(f, "        stop(process, \"SIGKILL\")")?;
+        writeln!(f, "        code = 124")?;
+        writeln!(f, "    except KeyboardInterrupt:")?;
+        writeln!(f, "        stop(process, \"SIGINT\")")?;
+        writeln!(f, "        exit(130)")?;
+      },
+      None => writeln!(f, "    code = Popen(program + argv[1:]{}).wait()", self.redirections())?
+    }
+    writeln!(f, "    if code == 0:")?;
+    writeln!(f, "        break")?;
+    writeln!(f, "    print(f\"Attempt {{attempt}} of {} failed with code {{code}}\", file=stderr)", attempts)?;
+    write!(f, "exit(code)")
+  }
+}
+
+impl Display for Script<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.interpreter {
+      _ if self.execution.is_empty() || !self.interpreter.applies_limits() => self.template(f),
+      Interpreter::Bash => self.bash_retried(f),
+      _ => self.python_retried(f)
+    }
+  }
 }
 
 script_display! {
   Bash => "#!/usr/bin/env bash
-{} {} \"$@\"
-exit $?" [sep " " wrap "\"{}\""]
+{} {} \"$@\"{}
+exit $?" [sep " " quote quote_posix]
 
   Cmd Batch => "@ECHO off
-{} {} %*
-EXIT /b %errorlevel%" [sep " " wrap-spaces "\"{}\""]
+{} {} %*{}
+EXIT /b %errorlevel%" [sep " " quote quote_cmd]
 
   Powershell => "$process = {}
 [String[]]$nArgs = {}
-$options = if ($nArgs.Count -or $args.Count) {{ @{{ args = $nArgs + $args }} }} else {{ @{{}} }}
+$options = if ($nArgs.Count -or $args.Count) {{ @{{ args = $nArgs + $args }} }} else {{ @{{}} }}{}
 Start-Process $process @options
-exit $LASTEXITCODE" [sep ", " wrap "\"{}\""]
+exit $LASTEXITCODE" [sep ", " quote quote_powershell empty "@()"]
 
   Python => "#!/usr/bin/env python
-from subprocess import run
+from subprocess import run, DEVNULL
 from sys import argv
 
 program = [{}, {}]
 
-code = run(program + argv[1:]).returncode
-exit(code)" [sep ", " wrap "\"{}\""]
+code = run(program + argv[1:]{}).returncode
+exit(code)" [sep ", " quote quote_python]
 
   Pythonw => "#!/usr/bin/env pythonw
-from subprocess import run
+from subprocess import run, DEVNULL
 from sys import argv
 
 program = [{}, {}]
 
-code = run(program + argv[1:]).returncode
-exit(code)" [sep ", " wrap "\"{}\""]
+code = run(program + argv[1:]{}).returncode
+exit(code)" [sep ", " quote quote_python]
 
   Ruby => "#!/usr/bin/env ruby
-system(\"{} {}\")
-$?.exitstatus" [sep " " wrap "\\\"{}\\\""]
+system(\"{} {}\"{})
+$?.exitstatus" [sep " " quote quote_ruby]
+}
+
+macro_rules! definition_display {
+  {$($($variant:ident)* => $template:literal [$($options:tt)*])*} => {
+    impl Display for Definition<'_> {
+      fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.shell {
+          $(
+            $(Shell::$variant)|* => write!(
+              f,
+              $template,
+              self.name,
+              script_options_display!([&self.binary], $($options)*),
+              script_options_display!(self.args, $($options)*)
+            ),
+          )*
+        }
+      }
+    }
+  };
+}
+
+definition_display! {
+  Bash Zsh => "{}() {{ {} {} \"$@\"; }}" [sep " " quote quote_posix]
+
+  Fish => "function {}
+  {} {} $argv
+end" [sep " " quote quote_fish]
+
+  PowershellProfile => "function {} {{ & {} {} @args }}" [sep " " quote quote_powershell]
 }
@@ -1,30 +1,84 @@
 use serde::{Serialize, Deserialize};
-use std::{fs, path, ops::{Deref, DerefMut}};
+use std::{fs, path, iter, rc::Rc, ops::{Deref, DerefMut}, time::Duration};
 
-use crate::{errors::{Result, ScuError}, interpreter::Interpreter, paths, startup::StartupReference, script::Script};
+use crate::{
+  errors::{Result, ScuError},
+  interpreter::Interpreter,
+  paths,
+  startup::StartupReference,
+  script::{Script, Definition, Shell},
+  streams::Streams,
+  execution::Execution,
+  resource::Resource,
+  store::{self, ShortcutStore},
+  editor,
+  migration
+};
 
+/// Template bound to the store it is kept in, dereferences to the [`Shortcut`]
 #[derive(Debug)]
 pub struct ShortcutFile {
   content: Shortcut,
-  path: path::PathBuf
+  key: String,
+  store: Rc<dyn ShortcutStore>,
+  version: u32
 }
 
 impl ShortcutFile {
-  pub fn new(content: Shortcut, path: path::PathBuf) -> Self {
-    Self { content, path }
+  /// Binds the template to a key of the store without storing it
+  pub fn new(content: Shortcut, key: impl AsRef<str>, store: Rc<dyn ShortcutStore>) -> Self {
+    Self { content, key: key.as_ref().to_string(), store, version: migration::CURRENT_VERSION }
   }
 
-  pub fn load(path: impl AsRef<path::Path>) -> Result<Self> {
-    toml::from_str(fs::read_to_string(&path)?.as_str())
-      .map_err(|err| err.into()).map(|content| ShortcutFile {
-        content,
-        path: path.as_ref().to_path_buf()
-      })
+  /// Loads the template upgrading it from older schema versions
+  pub fn load(store: Rc<dyn ShortcutStore>, key: impl AsRef<str>) -> Result<Self> {
+    let (content, version) = store.load(key.as_ref())?;
+    Ok(ShortcutFile { content, key: key.as_ref().to_string(), store, version })
   }
 
+  /// Key the template is kept under in the store
+  pub fn key(&self) -> &str {
+    &self.key
+  }
+
+  /// Schema version the file was written in, it is only updated when the template is stored again
+  pub fn version(&self) -> u32 {
+    self.version
+  }
+
+  /// Writes the template in the current schema version
   pub fn store(&self) -> Result<()> {
-    toml::to_string_pretty(&self.content).map_err(|err| ScuError::from(err))
-      .and_then(|data| fs::write(&self.path, data).map_err(|err| err.into()))
+    self.store.save(&self.key, &self.content)
+  }
+
+  /// Removes the template from the store, generated scripts are left untouched
+  pub fn remove(&self) -> Result<()> {
+    self.store.delete(&self.key)
+  }
+
+  /// Opens the template in the user editor until it is valid for `validate`, the changes are not stored
+  pub fn edit_template(&mut self, validate: impl Fn(&Shortcut) -> Result<()>) -> Result<()> {
+    let source = migration::to_string(&self.content)?;
+    let file_name = format!("{}{}", self.key, store::SUFFIX);
+    self.content = editor::edit_validated(source, file_name, |data| {
+      let (content, _) = migration::from_str(data)?;
+      if content.name != self.name {
+        return Err(ScuError::name_error(&self.name, "can not be changed while editing, use rename instead"))
+      }
+      validate(&content).map(|_| content)
+    })?;
+    Ok(())
+  }
+
+  /// Opens the script body in the user editor, the changes are not stored
+  pub fn edit_body(&mut self) -> Result<()> {
+    match &mut self.content.body {
+      ShortcutBody::CommandWithScript { script, body, .. } => {
+        let file_name = script.file_name().unwrap_or_default().to_string_lossy().to_string();
+        editor::edit(&body, file_name).map(|edited| *body = edited)
+      },
+      ShortcutBody::Command(_) => Ok(())
+    }
   }
 }
 
@@ -42,26 +96,94 @@ impl DerefMut for ShortcutFile {
   }
 }
 
+/// Template of a command shortcut, the scripts for each interpreter are made from it
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Shortcut {
   pub name: String,
+  pub aliases: Option<Vec<String>>,
+  pub description: Option<String>,
+  pub tags: Option<Vec<String>>,
   pub interpreters: Option<Vec<Interpreter>>,
+  pub streams: Option<Streams>,
+  /// Seconds an attempt can run before its process group is killed
+  pub timeout: Option<u64>,
+  /// Attempts made after a failed one, waiting longer before each
+  pub retries: Option<u32>,
+  pub resources: Option<Vec<Resource>>,
   pub body: ShortcutBody,
   pub startup: Option<StartupReference>
 }
 
 impl Shortcut {
+  /// Starts building a template, a name and a body are required
   pub fn builder() -> ShortcutBuilder {
     ShortcutBuilder::new()
   }
   
+  /// Script calling the command from the given interpreter
   pub fn script<'a>(&self, interpreter: &'a Interpreter) -> Result<Script<'a>> {
     Script::new(interpreter, self.command())
+      .map(|script| script.streams(self.streams.clone().unwrap_or_default()).execution(self.execution()))
   }
 
+  /// Full command line with the placeholders of the resources replaced by their paths
+  pub fn command(&self) -> Vec<String> {
+    let resources = self.resources.as_deref().unwrap_or_default();
+    self.body.command().iter().map(|arg| Resource::expand(arg, resources)).collect()
+  }
+
+  /// Paths the script of the body and the resources are written to
+  pub fn resource_paths(&self) -> Vec<&path::Path> {
+    let script = match &self.body {
+      ShortcutBody::CommandWithScript { script, .. } => Some(script.as_path()),
+      ShortcutBody::Command(_) => None
+    };
+    script.into_iter().chain(self.resources.iter().flatten().map(|resource| resource.path.as_path())).collect()
+  }
+
+  /// Writes the script of the body and every resource to their paths
+  pub fn write_resources(&self) -> Result<()> {
+    self.body.write_resources()?;
+    self.resources.iter().flatten().try_for_each(Resource::write)
+  }
+
+  /// Limits the command is run with
+  pub fn execution(&self) -> Execution {
+    Execution { timeout: self.timeout.map(Duration::from_secs), retries: self.retries.unwrap_or_default() }
+  }
+
+  /// Shell function definition calling the command under the given name
+  pub fn definition<'a>(&self, shell: &'a Shell, name: impl AsRef<str>) -> Result<Definition<'a>> {
+    Definition::new(shell, name, self.command())
+  }
+
+  /// Replaces the startup entry the template is registered with
   pub fn update_startup_reference(&mut self, startup: Option<StartupReference>) {
     self.startup = startup
   }
+
+  /// The name followed by the aliases
+  pub fn names(&self) -> impl Iterator<Item = &String> {
+    iter::once(&self.name).chain(self.aliases.iter().flatten())
+  }
+
+  /// Whether the name or any alias matches
+  pub fn is_named(&self, name: impl AsRef<str>) -> bool {
+    self.names().any(|x| x == name.as_ref())
+  }
+
+  /// Whether the template is tagged with the given tag
+  pub fn has_tag(&self, tag: impl AsRef<str>) -> bool {
+    self.tags.iter().flatten().any(|x| x == tag.as_ref())
+  }
+
+  /// Whether scripts are made for the interpreter, every interpreter is targeted when none is set
+  pub fn targets(&self, interpreter: &Interpreter) -> bool {
+    match &self.interpreters {
+      Some(interpreters) => interpreters.contains(interpreter),
+      None => Interpreter::all().contains(interpreter)
+    }
+  }
 }
 
 impl Deref for Shortcut {
@@ -72,6 +194,7 @@ impl Deref for Shortcut {
   }
 }
 
+/// What the shortcut runs, either a plain command or a command over a script stored as resource
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(tag = "type", content = "command")]
 pub enum ShortcutBody {
@@ -85,20 +208,25 @@ pub enum ShortcutBody {
 }
 
 impl ShortcutBody {
+  /// Full command line, with the resource path inserted at its offset for script-backed bodies
   pub fn command(&self) -> Vec<String> {
     match self {
       Self::Command(cmd) => cmd.clone(),
       Self::CommandWithScript { cmd, script, body: _, script_offset } => {
         let mut command = cmd.clone();
-        command.insert(script_offset.unwrap_or(command.len() as u8).into(), paths::stringify(script, "\\\\"));
+        command.insert(script_offset.unwrap_or(command.len() as u8).into(), paths::stringify(script, paths::SCRIPT_SEPARATOR));
         command
       },
     }
   }
 
+  /// Writes the script of script-backed bodies to its resource path
   pub fn write_resources(&self) -> Result<()> {
     match self {
-      Self::CommandWithScript { cmd: _, script, body, script_offset: _ } => fs::write(script, body).map_err(|err| err.into()),
+      Self::CommandWithScript { cmd: _, script, body, script_offset: _ } => script.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| paths::write_atomic(script, body, false))
+        .map_err(|err| ScuError::ResourceError { path: script.clone(), source: Some(err) }),
       _ => Ok(())
     }
   }
@@ -113,9 +241,17 @@ impl std::fmt::Display for ShortcutBody {
   }
 }
 
+/// Builder of [`Shortcut`] templates
 pub struct ShortcutBuilder {
   pub name: Option<String>,
+  pub aliases: Option<Vec<String>>,
+  pub description: Option<String>,
+  pub tags: Option<Vec<String>>,
   pub interpreters: Option<Vec<Interpreter>>,
+  pub streams: Option<Streams>,
+  pub timeout: Option<u64>,
+  pub retries: Option<u32>,
+  pub resources: Option<Vec<Resource>>,
   pub body: Option<ShortcutBody>
 }
 
@@ -123,7 +259,14 @@ impl ShortcutBuilder {
   pub fn new() -> Self {
     ShortcutBuilder {
       name: None,
+      aliases: None,
+      description: None,
+      tags: None,
       interpreters: None,
+      streams: None,
+      timeout: None,
+      retries: None,
+      resources: None,
       body: None
     }
   }
@@ -133,11 +276,46 @@ impl ShortcutBuilder {
     self
   }
   
+  pub fn aliases(mut self, aliases: Option<Vec<String>>) -> Self {
+    self.aliases = aliases.filter(|aliases| !aliases.is_empty());
+    self
+  }
+
+  pub fn description(mut self, description: Option<String>) -> Self {
+    self.description = description;
+    self
+  }
+
+  pub fn tags(mut self, tags: Option<Vec<String>>) -> Self {
+    self.tags = tags.filter(|tags| !tags.is_empty());
+    self
+  }
+
   pub fn interpreters(mut self, interpreters: Option<Vec<Interpreter>>) -> Self {
     self.interpreters = interpreters;
     self
   }
 
+  pub fn streams(mut self, streams: Option<Streams>) -> Self {
+    self.streams = streams.filter(|streams| !streams.is_empty());
+    self
+  }
+
+  pub fn timeout(mut self, timeout: Option<u64>) -> Self {
+    self.timeout = timeout.filter(|timeout| *timeout > 0);
+    self
+  }
+
+  pub fn retries(mut self, retries: Option<u32>) -> Self {
+    self.retries = retries.filter(|retries| *retries > 0);
+    self
+  }
+
+  pub fn resources(mut self, resources: Option<Vec<Resource>>) -> Self {
+    self.resources = resources.filter(|resources| !resources.is_empty());
+    self
+  }
+
   pub fn command(mut self, command: Vec<String>) -> Self {
     self.body = Some(ShortcutBody::Command(command));
     self
@@ -159,12 +337,47 @@ impl ShortcutBuilder {
     Ok(self)
   }
 
+  /// Builds the template, panics if the name or the body are missing
   pub fn build(self) -> Shortcut {
     Shortcut {
       name: self.name.unwrap(),
+      aliases: self.aliases,
+      description: self.description,
+      tags: self.tags,
       interpreters: self.interpreters,
+      streams: self.streams,
+      timeout: self.timeout,
+      retries: self.retries,
+      resources: self.resources,
       body: self.body.unwrap(),
       startup: None
     }
   }
-}
\ No newline at end of file
+}
+impl Default for ShortcutBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Criteria to select templates, every set criterion must match
+#[derive(Debug, Default)]
+pub struct ShortcutFilter {
+  pub tags: Vec<String>,
+  pub grep: Option<String>,
+  pub interpreter: Option<Interpreter>,
+  pub startup: bool
+}
+
+impl ShortcutFilter {
+  pub fn matches(&self, shortcut: &Shortcut) -> bool {
+    self.tags.iter().all(|tag| shortcut.has_tag(tag))
+      && self.grep.as_ref().map(|pattern| {
+        let pattern = pattern.to_lowercase();
+        shortcut.names().chain([Some(&shortcut.body.to_string()), shortcut.description.as_ref()].into_iter().flatten())
+          .any(|field| field.to_lowercase().contains(&pattern))
+      }).unwrap_or(true)
+      && self.interpreter.as_ref().map(|interpreter| shortcut.targets(interpreter)).unwrap_or(true)
+      && (!self.startup || shortcut.startup.is_some())
+  }
+}
@@ -16,7 +16,7 @@ mod windows_startup {
   impl StartupReference {
     pub fn create(shortcut: &Shortcut) -> Result<Self> {
       let interpreter = Interpreter::Cmd;
-      let path = home_dir().ok_or(ScuError::StringError("".into()))?.join(STARTUP_WIN_PATH)
+      let path = home_dir().ok_or(ScuError::HomeNotFound)?.join(STARTUP_WIN_PATH)
         .join(format!("{}{}", shortcut.name, interpreter.extension()));
       fs::write(&path, format!("{}", shortcut.script(&interpreter)?))?;
       Ok(StartupReference(path))
@@ -25,6 +25,10 @@ mod windows_startup {
     pub fn delete(&self) -> Result<()> {
       fs::remove_file(&self.0).map_err(|err| err.into())
     }
+
+    pub fn path(&self) -> &path::Path {
+      &self.0
+    }
   }
 
   impl Display for StartupReference {
@@ -35,4 +39,39 @@ mod windows_startup {
 }
 
 #[cfg(target_os = "windows")]
-pub use windows_startup::*;
\ No newline at end of file
+pub use windows_startup::*;
+
+#[cfg(not(target_os = "windows"))]
+mod default_startup {
+  use std::{path, fmt::Display};
+
+  use serde::{Serialize, Deserialize};
+
+  use crate::{shortcut::Shortcut, errors::{ScuError, Result}};
+
+  #[derive(Clone, Debug, Serialize, Deserialize)]
+  pub struct StartupReference(path::PathBuf);
+
+  impl StartupReference {
+    pub fn create(_shortcut: &Shortcut) -> Result<Self> {
+      Err(ScuError::Unsupported { feature: "Startup" })
+    }
+
+    pub fn delete(&self) -> Result<()> {
+      Err(ScuError::Unsupported { feature: "Startup" })
+    }
+
+    pub fn path(&self) -> &path::Path {
+      &self.0
+    }
+  }
+
+  impl Display for StartupReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+      write!(f, "{}", self.0.display())
+    }
+  }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub use default_startup::*;
@@ -0,0 +1,303 @@
+use std::{fs, path, fmt, cell::RefCell, collections::BTreeMap};
+
+use serde::{Serialize, Deserialize};
+
+use crate::{shortcut::Shortcut, errors::{Result, ScuError}, migration, paths};
+
+pub const SUFFIX: &str = ".toml";
+
+/// Persistence of the shortcut templates, each one is identified by a key which is the shortcut name
+pub trait ShortcutStore: fmt::Debug {
+  /// Keys of every stored template, sorted
+  fn list(&self) -> Result<Vec<String>>;
+
+  /// Template with the schema version it was stored in
+  fn load(&self, key: &str) -> Result<(Shortcut, u32)>;
+
+  fn save(&self, key: &str, shortcut: &Shortcut) -> Result<()>;
+
+  fn delete(&self, key: &str) -> Result<()>;
+
+  fn rename(&self, key: &str, new_key: &str) -> Result<()>;
+
+  fn contains(&self, key: &str) -> bool {
+    self.list().map(|keys| keys.iter().any(|x| x == key)).unwrap_or(false)
+  }
+
+  /// Creates what the store needs to be written to, nothing by default
+  fn setup(&self) -> Result<()> {
+    Ok(())
+  }
+
+  /// Copies the stored data of the template into the directory before it is rewritten, nothing to copy by default
+  fn backup(&self, _key: &str, _version: u32, _dir: &path::Path) -> Result<()> {
+    Ok(())
+  }
+}
+
+/// Kind of store used for the templates, set in the configuration file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StoreKind {
+  /// One TOML file per template in the `meta` directory
+  #[default]
+  Directory,
+  /// Every template in a single TOML file
+  SingleFile
+}
+
+fn missing(key: &str) -> ScuError {
+  ScuError::ShortcutNotFound { name: key.to_string(), suggestions: Vec::new() }
+}
+
+/// One `<name>.toml` file per template
+#[derive(Debug)]
+pub struct DirectoryStore {
+  dir: path::PathBuf
+}
+
+impl DirectoryStore {
+  pub fn new(dir: path::PathBuf) -> Self {
+    DirectoryStore { dir }
+  }
+
+  pub fn path(&self, key: &str) -> path::PathBuf {
+    self.dir.join(format!("{}{}", key, SUFFIX))
+  }
+}
+
+impl ShortcutStore for DirectoryStore {
+  fn setup(&self) -> Result<()> {
+    fs::create_dir_all(&self.dir).map_err(|err| err.into())
+  }
+
+  fn list(&self) -> Result<Vec<String>> {
+    let mut keys: Vec<String> = fs::read_dir(&self.dir)?.filter_map(|x| x.ok())
+      .map(|entry| entry.file_name().to_string_lossy().to_string())
+      .filter(|file_name| !file_name.starts_with('.'))
+      .filter_map(|file_name| file_name.strip_suffix(SUFFIX).map(String::from))
+      .collect();
+    keys.sort();
+    Ok(keys)
+  }
+
+  fn load(&self, key: &str) -> Result<(Shortcut, u32)> {
+    let path = self.path(key);
+    if !path.exists() {
+      return Err(missing(key))
+    }
+    migration::from_str(fs::read_to_string(&path)?.as_str())
+      .map_err(|source| ScuError::InvalidShortcut { path, source })
+  }
+
+  fn save(&self, key: &str, shortcut: &Shortcut) -> Result<()> {
+    paths::write_atomic(self.path(key), migration::to_string(shortcut)?, false).map_err(|err| err.into())
+  }
+
+  fn delete(&self, key: &str) -> Result<()> {
+    fs::remove_file(self.path(key)).map_err(|err| err.into())
+  }
+
+  fn rename(&self, key: &str, new_key: &str) -> Result<()> {
+    fs::rename(self.path(key), self.path(new_key)).map_err(|err| err.into())
+  }
+
+  fn contains(&self, key: &str) -> bool {
+    self.path(key).exists()
+  }
+
+  fn backup(&self, key: &str, version: u32, dir: &path::Path) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::copy(self.path(key), dir.join(format!("{}.v{}{}", key, version, SUFFIX)))?;
+    Ok(())
+  }
+}
+
+/// Every template as a table of a single file, keyed by its name
+#[derive(Debug)]
+pub struct SingleFileStore {
+  file: path::PathBuf
+}
+
+impl SingleFileStore {
+  pub fn new(file: path::PathBuf) -> Self {
+    SingleFileStore { file }
+  }
+
+  fn read(&self) -> Result<toml::Table> {
+    if !self.file.exists() {
+      return Ok(toml::Table::new())
+    }
+    toml::from_str(fs::read_to_string(&self.file)?.as_str())
+      .map_err(|source| ScuError::InvalidShortcut { path: self.file.clone(), source })
+  }
+
+  fn write(&self, table: &toml::Table) -> Result<()> {
+    paths::write_atomic(&self.file, toml::to_string_pretty(table)?, false).map_err(|err| err.into())
+  }
+}
+
+impl ShortcutStore for SingleFileStore {
+  fn list(&self) -> Result<Vec<String>> {
+    Ok(self.read()?.keys().cloned().collect())
+  }
+
+  fn load(&self, key: &str) -> Result<(Shortcut, u32)> {
+    match self.read()?.remove(key) {
+      Some(toml::Value::Table(table)) => migration::from_table(table),
+      Some(_) => Err(serde::de::Error::custom("expecting a table")),
+      None => return Err(missing(key))
+    }.map_err(|err: toml::de::Error| ScuError::InvalidShortcut {
+      path: self.file.clone(),
+      source: serde::de::Error::custom(format!("{}: {}", key, err))
+    })
+  }
+
+  fn save(&self, key: &str, shortcut: &Shortcut) -> Result<()> {
+    let mut table = self.read()?;
+    table.insert(key.to_string(), toml::Value::Table(migration::to_table(shortcut)?));
+    self.write(&table)
+  }
+
+  fn delete(&self, key: &str) -> Result<()> {
+    let mut table = self.read()?;
+    table.remove(key).ok_or_else(|| missing(key))?;
+    self.write(&table)
+  }
+
+  fn rename(&self, key: &str, new_key: &str) -> Result<()> {
+    let mut table = self.read()?;
+    let value = table.remove(key).ok_or_else(|| missing(key))?;
+    table.insert(new_key.to_string(), value);
+    self.write(&table)
+  }
+
+  fn backup(&self, _key: &str, version: u32, dir: &path::Path) -> Result<()> {
+    let stem = self.file.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    fs::create_dir_all(dir)?;
+    fs::copy(&self.file, dir.join(format!("{}.v{}{}", stem, version, SUFFIX)))?;
+    Ok(())
+  }
+}
+
+/// Templates kept in memory, nothing survives the process
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+  shortcuts: RefCell<BTreeMap<String, Shortcut>>
+}
+
+impl MemoryStore {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl ShortcutStore for MemoryStore {
+  fn list(&self) -> Result<Vec<String>> {
+    Ok(self.shortcuts.borrow().keys().cloned().collect())
+  }
+
+  fn load(&self, key: &str) -> Result<(Shortcut, u32)> {
+    self.shortcuts.borrow().get(key).cloned().map(|shortcut| (shortcut, migration::CURRENT_VERSION)).ok_or_else(|| missing(key))
+  }
+
+  fn save(&self, key: &str, shortcut: &Shortcut) -> Result<()> {
+    self.shortcuts.borrow_mut().insert(key.to_string(), shortcut.clone());
+    Ok(())
+  }
+
+  fn delete(&self, key: &str) -> Result<()> {
+    self.shortcuts.borrow_mut().remove(key).map(|_| ()).ok_or_else(|| missing(key))
+  }
+
+  fn rename(&self, key: &str, new_key: &str) -> Result<()> {
+    let mut shortcuts = self.shortcuts.borrow_mut();
+    let shortcut = shortcuts.remove(key).ok_or_else(|| missing(key))?;
+    shortcuts.insert(new_key.to_string(), shortcut);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  fn shortcut(name: &str) -> Shortcut {
+    Shortcut::builder().name(name).command(vec!["echo".into(), name.into()]).build()
+  }
+
+  fn exercise(store: &dyn ShortcutStore) {
+    assert!(store.list().unwrap().is_empty());
+    store.save("b", &shortcut("b")).unwrap();
+    store.save("a", &shortcut("a")).unwrap();
+    assert_eq!(store.list().unwrap(), vec!["a", "b"]);
+    assert!(store.contains("a") && !store.contains("c"));
+
+    let (loaded, version) = store.load("a").unwrap();
+    assert_eq!(loaded.name, "a");
+    assert_eq!(loaded.command(), vec!["echo", "a"]);
+    assert_eq!(version, migration::CURRENT_VERSION);
+
+    store.save("a", &shortcut("updated")).unwrap();
+    assert_eq!(store.load("a").unwrap().0.name, "updated");
+
+    store.rename("a", "c").unwrap();
+    assert_eq!(store.list().unwrap(), vec!["b", "c"]);
+    assert_eq!(store.load("c").unwrap().0.name, "updated");
+
+    store.delete("b").unwrap();
+    assert_eq!(store.list().unwrap(), vec!["c"]);
+    assert!(matches!(store.load("b"), Err(ScuError::ShortcutNotFound { .. })));
+    assert!(store.delete("b").is_err());
+    assert!(store.rename("b", "d").is_err());
+  }
+
+  #[test]
+  fn memory_store() {
+    exercise(&MemoryStore::new());
+  }
+
+  #[test]
+  fn directory_store() {
+    let dir = TempDir::new().unwrap();
+    let store = DirectoryStore::new(dir.path().join("meta"));
+    store.setup().unwrap();
+    exercise(&store);
+    fs::write(dir.path().join("meta").join(".hidden.toml"), "").unwrap();
+    fs::write(dir.path().join("meta").join("notes.txt"), "").unwrap();
+    assert_eq!(store.list().unwrap(), vec!["c"]);
+  }
+
+  #[test]
+  fn single_file_store() {
+    let dir = TempDir::new().unwrap();
+    let store = SingleFileStore::new(dir.path().join("shortcuts.toml"));
+    store.setup().unwrap();
+    exercise(&store);
+  }
+
+  #[test]
+  fn invalid_templates_report_their_location() {
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path();
+    fs::write(dir.join("bad.toml"), "name = ").unwrap();
+    let store = DirectoryStore::new(dir.to_path_buf());
+    assert_eq!(store.list().unwrap(), vec!["bad"]);
+    assert!(matches!(store.load("bad"), Err(ScuError::InvalidShortcut { path, .. }) if path == dir.join("bad.toml")));
+
+    fs::write(dir.join("shortcuts.toml"), "[bad]\nname = 1\n").unwrap();
+    let store = SingleFileStore::new(dir.join("shortcuts.toml"));
+    assert_eq!(store.list().unwrap(), vec!["bad"]);
+    assert!(matches!(store.load("bad"), Err(ScuError::InvalidShortcut { path, .. }) if path == dir.join("shortcuts.toml")));
+  }
+
+  #[test]
+  fn configured_kind_defaults_to_directory() {
+    assert_eq!(StoreKind::default(), StoreKind::Directory);
+    let config: crate::config::Config = toml::from_str("store = \"single-file\"").unwrap();
+    assert_eq!(config.store, StoreKind::SingleFile);
+    let config: crate::config::Config = toml::from_str("").unwrap();
+    assert_eq!(config.store, StoreKind::Directory);
+  }
+}
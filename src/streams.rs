@@ -0,0 +1,65 @@
+use serde::{Serialize, Deserialize};
+use std::{fs, path, process};
+
+use crate::errors::{Result, ScuError};
+
+fn is_false(value: &bool) -> bool {
+  !value
+}
+
+/// Redirection of the standard streams of a shortcut, streams without a file are inherited unless quiet
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Streams {
+  pub stdin: Option<path::PathBuf>,
+  pub stdout: Option<path::PathBuf>,
+  pub stderr: Option<path::PathBuf>,
+  /// Append to the output files instead of truncating them
+  #[serde(default, skip_serializing_if = "is_false")]
+  pub append: bool,
+  /// Discard the output streams that are not written to a file
+  #[serde(default, skip_serializing_if = "is_false")]
+  pub quiet: bool
+}
+
+impl Streams {
+  pub fn is_empty(&self) -> bool {
+    *self == Streams::default()
+  }
+
+  /// Streams set here take precedence over the ones of `other`
+  pub fn or(&self, other: &Streams) -> Streams {
+    Streams {
+      stdin: self.stdin.clone().or_else(|| other.stdin.clone()),
+      stdout: self.stdout.clone().or_else(|| other.stdout.clone()),
+      stderr: self.stderr.clone().or_else(|| other.stderr.clone()),
+      append: self.append || other.append,
+      quiet: self.quiet || other.quiet
+    }
+  }
+
+  /// Resolves the files against the current directory, so they stay valid when stored
+  pub fn absolute(&self) -> Result<Streams> {
+    let resolve = |file: &Option<path::PathBuf>| file.as_ref().map(path::absolute).transpose();
+    Ok(Streams { stdin: resolve(&self.stdin)?, stdout: resolve(&self.stdout)?, stderr: resolve(&self.stderr)?, ..self.clone() })
+  }
+
+  fn output(&self, file: &Option<path::PathBuf>) -> Result<process::Stdio> {
+    match file {
+      Some(file) => fs::OpenOptions::new().create(true).write(true).append(self.append).truncate(!self.append).open(file)
+        .map(process::Stdio::from)
+        .map_err(|err| ScuError::ResourceError { path: file.clone(), source: Some(err) }),
+      None if self.quiet => Ok(process::Stdio::null()),
+      None => Ok(process::Stdio::inherit())
+    }
+  }
+
+  /// Opens the files and connects them to the command
+  pub fn apply(&self, command: &mut process::Command) -> Result<()> {
+    if let Some(file) = &self.stdin {
+      command.stdin(fs::File::open(file).map_err(|err| ScuError::ResourceError { path: file.clone(), source: Some(err) })?);
+    }
+    command.stdout(self.output(&self.stdout)?);
+    command.stderr(self.output(&self.stderr)?);
+    Ok(())
+  }
+}
@@ -0,0 +1,59 @@
+fn distance(a: &str, b: &str) -> usize {
+  let a: Vec<char> = a.chars().collect();
+  let b: Vec<char> = b.chars().collect();
+  let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+  (0..=a.len()).for_each(|i| table[i][0] = i);
+  (0..=b.len()).for_each(|j| table[0][j] = j);
+  for i in 1..=a.len() {
+    for j in 1..=b.len() {
+      let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+      table[i][j] = (table[i - 1][j] + 1).min(table[i][j - 1] + 1).min(table[i - 1][j - 1] + cost);
+      if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+        table[i][j] = table[i][j].min(table[i - 2][j - 2] + 1);
+      }
+    }
+  }
+  table[a.len()][b.len()]
+}
+
+const MAX_SUGGESTIONS: usize = 3;
+
+pub fn closest<'a>(target: impl AsRef<str>, candidates: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+  let target = target.as_ref().to_lowercase();
+  let threshold = (target.chars().count() / 3).max(1);
+  let mut found: Vec<(usize, &str)> = candidates.into_iter()
+    .map(|candidate| (distance(&target, &candidate.to_lowercase()), candidate))
+    .filter(|(distance, _)| *distance <= threshold)
+    .collect();
+  found.sort();
+  found.dedup_by(|a, b| a.1 == b.1);
+  found.into_iter().take(MAX_SUGGESTIONS).map(|(_, candidate)| candidate.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn transpositions_count_as_one_edit() {
+    assert_eq!(distance("grep", "gerp"), 1);
+    assert_eq!(distance("ab", "ba"), 1);
+    assert_eq!(distance("kitten", "sitting"), 3);
+    assert_eq!(distance("", "abc"), 3);
+    assert_eq!(distance("same", "same"), 0);
+  }
+
+  #[test]
+  fn closest_keeps_the_candidates_within_the_threshold() {
+    let candidates = ["deploy", "delpoy", "destroy", "build", "Deploy2"];
+    assert_eq!(closest("deplyo", candidates), vec!["deploy", "Deploy2", "delpoy"]);
+    // Short targets still accept a single edit
+    assert_eq!(closest("bs", ["ls", "cd", "b"]), vec!["b", "ls"]);
+    assert!(closest("build", ["deploy"]).is_empty());
+  }
+
+  #[test]
+  fn closest_without_candidates_is_empty() {
+    assert!(closest("anything", []).is_empty());
+  }
+}
@@ -0,0 +1,85 @@
+use std::{path, process};
+
+use crate::{errors::{Result, ScuError}, paths};
+
+const REMOTE: &str = "origin";
+const BRANCH: &str = "main";
+const IGNORE: &str = "/*\n!/.gitignore\n!/config.toml\n!/shortcuts.toml\n!/meta/\n!/res/\n";
+const MESSAGE: &str = "Update shortcuts";
+
+pub struct Sync {
+  root: path::PathBuf
+}
+
+impl Sync {
+  pub fn new(root: path::PathBuf) -> Self {
+    Sync { root }
+  }
+
+  pub fn is_initialized(&self) -> bool {
+    self.root.join(".git").exists()
+  }
+
+  fn git(&self, args: &[&str]) -> Result<String> {
+    let output = process::Command::new("git").arg("-C").arg(&self.root).args(args).output()?;
+    if output.status.success() {
+      Ok(String::from_utf8(output.stdout)?)
+    } else {
+      Err(ScuError::GitError {
+        args: args.join(" "),
+        message: String::from_utf8_lossy(&output.stderr).trim().to_string()
+      })
+    }
+  }
+
+  fn git_as_author(&self, args: &[&str]) -> Result<String> {
+    if self.git(&["config", "user.email"]).map(|x| !x.trim().is_empty()).unwrap_or(false) {
+      self.git(args)
+    } else {
+      self.git(&[["-c", "user.name=scu", "-c", "user.email=scu@localhost"].as_slice(), args].concat())
+    }
+  }
+
+  pub fn init(&self, remote: impl AsRef<str>) -> Result<()> {
+    if !self.is_initialized() {
+      self.git(&["init", "-q"])?;
+      self.git(&["symbolic-ref", "HEAD", &format!("refs/heads/{}", BRANCH)])?;
+    }
+    paths::write_atomic(self.root.join(".gitignore"), IGNORE, false)?;
+    if self.git(&["remote", "get-url", REMOTE]).is_ok() {
+      self.git(&["remote", "set-url", REMOTE, remote.as_ref()])?;
+    } else {
+      self.git(&["remote", "add", REMOTE, remote.as_ref()])?;
+    }
+    self.commit()?;
+    Ok(())
+  }
+
+  fn ensure_initialized(&self) -> Result<()> {
+    if self.is_initialized() {
+      Ok(())
+    } else {
+      Err(ScuError::SyncNotInitialized)
+    }
+  }
+
+  pub fn commit(&self) -> Result<bool> {
+    self.git(&["add", "-A"])?;
+    if self.git(&["status", "--porcelain"])?.trim().is_empty() {
+      return Ok(false)
+    }
+    self.git_as_author(&["commit", "-q", "-m", MESSAGE]).map(|_| true)
+  }
+
+  pub fn push(&self) -> Result<()> {
+    self.ensure_initialized()?;
+    self.commit()?;
+    self.git(&["push", "-q", "-u", REMOTE, BRANCH]).map(|_| ())
+  }
+
+  pub fn pull(&self) -> Result<()> {
+    self.ensure_initialized()?;
+    self.commit()?;
+    self.git_as_author(&["pull", "-q", "--no-rebase", "--no-edit", "--allow-unrelated-histories", REMOTE, BRANCH]).map(|_| ())
+  }
+}
@@ -0,0 +1,600 @@
+use std::{fs, path, process};
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+
+/// Data directory isolated per test, removed when dropped
+struct Env {
+  root: TempDir
+}
+
+impl Env {
+  fn new() -> Self {
+    Env { root: TempDir::new().unwrap() }
+  }
+
+  fn scu(&self) -> Command {
+    let mut command = Command::cargo_bin("scu").unwrap();
+    command.env("SCU_ROOT", self.root.path()).current_dir(self.root.path());
+    command
+  }
+
+  fn run(&self, args: &[&str]) -> assert_cmd::assert::Assert {
+    self.scu().args(args).assert()
+  }
+
+  fn data(&self) -> path::PathBuf {
+    self.root.path().join("scu_data")
+  }
+
+  fn bin(&self, name: &str) -> path::PathBuf {
+    self.data().join("bin").join(name)
+  }
+
+  fn write(&self, name: &str, contents: &str) {
+    fs::write(self.root.path().join(name), contents).unwrap();
+  }
+}
+
+fn has_command(name: &str) -> bool {
+  process::Command::new(name).arg("--version").output().map(|output| output.status.success()).unwrap_or(false)
+}
+
+#[test]
+fn set_stores_the_template_in_the_root() {
+  let env = Env::new();
+  env.run(&["set", "greet", "-i", "bash", "--", "echo", "hello"]).success();
+  let stored = fs::read_to_string(env.data().join("meta").join("greet.toml")).unwrap();
+  assert!(stored.contains("name = \"greet\""));
+  env.run(&["list"]).success().stdout("> greet => echo hello\n");
+}
+
+#[test]
+fn set_refuses_to_overwrite_without_force() {
+  let env = Env::new();
+  env.run(&["set", "greet", "--", "echo", "hello"]).success();
+  env.run(&["set", "greet", "--", "echo", "bye"]).failure().code(6);
+  env.run(&["set", "greet", "--force", "--", "echo", "bye"]).success().stderr(predicate::str::contains("Warning"));
+  env.run(&["list"]).success().stdout("> greet => echo bye\n");
+}
+
+#[test]
+fn unset_removes_the_template_and_its_scripts() {
+  let env = Env::new();
+  env.run(&["set", "greet", "-i", "bash", "-m", "--", "echo", "hello"]).success();
+  assert!(env.bin("greet").exists());
+  env.run(&["unset", "greet"]).success();
+  assert!(!env.bin("greet").exists());
+  env.run(&["list"]).success().stdout("");
+  env.run(&["unset", "greet"]).failure().code(3);
+}
+
+#[test]
+fn rename_moves_the_template_and_its_scripts() {
+  let env = Env::new();
+  env.run(&["set", "greet", "-i", "bash", "-m", "--", "echo", "hello"]).success();
+  env.run(&["rename", "greet", "salute"]).success();
+  assert!(!env.bin("greet").exists());
+  assert!(env.bin("salute").exists());
+  env.run(&["list"]).success().stdout("> salute => echo hello\n");
+  env.run(&["rename", "salut", "other"]).failure().code(3).stderr(predicate::str::contains("did you mean: salute?"));
+}
+
+#[test]
+fn list_filters_by_tag_and_interpreter() {
+  let env = Env::new();
+  env.run(&["set", "a", "-t", "web", "-i", "bash", "--", "echo", "a"]).success();
+  env.run(&["set", "b", "-i", "python", "--", "echo", "b"]).success();
+  env.run(&["list", "-t", "web"]).success().stdout("> a => echo a\n");
+  env.run(&["list", "-i", "python"]).success().stdout("> b => echo b\n");
+  env.run(&["list", "-i", "bahs"]).failure().code(4).stderr(predicate::str::contains("bash"));
+}
+
+#[test]
+fn list_reports_invalid_templates() {
+  let env = Env::new();
+  env.run(&["set", "a", "--", "echo", "a"]).success();
+  fs::write(env.data().join("meta").join("bad.toml"), "name = ").unwrap();
+  env.run(&["list"]).success().stdout("> a => echo a\n");
+  env.run(&["list", "-e"]).success().stdout(predicate::str::contains("> Invalid template: bad"));
+}
+
+#[test]
+fn make_generates_the_scripts_of_the_interpreters() {
+  let env = Env::new();
+  env.run(&["set", "greet", "-i", "bash", "python", "--", "echo", "hello"]).success();
+  env.run(&["make", "greet"]).success().stdout("Made 1 shortcut\n");
+  assert!(env.bin("greet").exists());
+  assert!(env.bin("greet.py").exists());
+  assert!(!env.bin("greet.rb").exists());
+  env.run(&["make", "greet", "-i", "ruby"]).success();
+  assert!(env.bin("greet.rb").exists());
+  env.run(&["make", "missing"]).failure().code(3);
+}
+
+#[test]
+fn clean_removes_the_generated_scripts() {
+  let env = Env::new();
+  env.run(&["set", "greet", "-i", "bash", "-m", "--", "echo", "hello"]).success();
+  env.run(&["clean"]).success();
+  assert!(!env.bin("greet").exists());
+  env.run(&["list"]).success().stdout("> greet => echo hello\n");
+}
+
+#[test]
+fn run_executes_the_command_with_the_arguments() {
+  let env = Env::new();
+  env.run(&["set", "greet", "--", "echo", "hello"]).success();
+  env.run(&["run", "greet", "world"]).success().stdout("hello world\n");
+}
+
+#[test]
+fn run_redirects_the_standard_streams() {
+  let env = Env::new();
+  env.write("input.txt", "from a file\n");
+  env.run(&["set", "copy", "--", "cat"]).success();
+  env.run(&["run", "copy", "--stdin-file", "input.txt", "--stdout", "out.txt"]).success().stdout("");
+  env.run(&["run", "copy", "--stdin-file", "input.txt", "--stdout", "out.txt", "--append"]).success();
+  assert_eq!(fs::read_to_string(env.root.path().join("out.txt")).unwrap(), "from a file\nfrom a file\n");
+  env.run(&["run", "copy", "--stdin-file", "input.txt", "--quiet"]).success().stdout("");
+  env.run(&["run", "copy", "--stdin-file", "missing.txt"]).failure().code(7);
+}
+
+#[cfg(unix)]
+#[test]
+fn stored_streams_apply_to_run_and_scripts() {
+  let env = Env::new();
+  env.run(&["set", "greet", "-i", "bash", "-m", "--stdout", "greet.log", "--append", "--", "echo", "hello"]).success();
+  env.run(&["run", "greet", "run"]).success().stdout("");
+  let output = process::Command::new(env.bin("greet")).arg("script").output().unwrap();
+  assert!(output.stdout.is_empty());
+  assert_eq!(fs::read_to_string(env.root.path().join("greet.log")).unwrap(), "hello run\nhello script\n");
+  env.run(&["run", "greet", "--stdout", "other.log", "again"]).success();
+  assert_eq!(fs::read_to_string(env.root.path().join("other.log")).unwrap(), "hello again\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn run_retries_failed_attempts() {
+  let env = Env::new();
+  let flaky = "echo attempt >> attempts; [ $(wc -l < attempts) -ge 2 ]";
+  env.run(&["set", "flaky", "--retries", "1", "--", "sh", "-c", flaky]).success();
+  env.run(&["run", "flaky"]).success().stdout("").stderr(predicate::str::contains("Warning: Shortcut flaky exited with code 1 on attempt 1 of 2"));
+  env.run(&["set", "broken", "--retries", "1", "--", "sh", "-c", "exit 3"]).success();
+  env.run(&["run", "broken"]).failure().code(13).stderr(predicate::str::contains("exited with code 3 on attempt 2 of 2"));
+  env.run(&["set", "plain", "--", "sh", "-c", "exit 3"]).success();
+  env.run(&["run", "plain"]).failure().code(13).stderr(predicate::str::contains("on attempt 1 of 1"));
+}
+
+#[cfg(unix)]
+#[test]
+fn run_kills_the_attempts_that_time_out() {
+  let env = Env::new();
+  env.run(&["set", "slow", "--timeout", "1", "--", "sh", "-c", "sleep 30 & sleep 30"]).success();
+  let start = std::time::Instant::now();
+  env.run(&["run", "slow"]).failure().code(13).stderr(predicate::str::contains("timed out after 1s on attempt 1 of 1"));
+  assert!(start.elapsed().as_secs() < 10);
+}
+
+#[cfg(unix)]
+#[test]
+fn run_does_not_retry_interrupted_attempts() {
+  let env = Env::new();
+  env.run(&["set", "stopped", "--timeout", "10", "--retries", "2", "--", "sh", "-c", "kill -INT $$; sleep 10"]).success();
+  env.run(&["run", "stopped"]).failure().code(13)
+    .stderr(predicate::str::contains(format!("was interrupted by signal {} on attempt 1 of 3", libc::SIGINT)));
+}
+
+#[test]
+fn make_warns_when_scripts_ignore_the_limits() {
+  let env = Env::new();
+  env.run(&["set", "fetch", "-i", "ruby", "--retries", "2", "--", "true"]).success();
+  env.run(&["make", "fetch"]).success()
+    .stderr(predicate::str::contains("Warning: The ruby script of fetch ignores its timeout and retries"));
+  env.run(&["set", "plain", "-i", "ruby", "--", "true"]).success();
+  env.run(&["make", "plain"]).success().stderr(predicate::str::contains("Warning").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn python_scripts_kill_the_attempts_that_time_out() {
+  if !has_command("python") {
+    return
+  }
+  let env = Env::new();
+  env.run(&["set", "slow", "-i", "python", "-m", "--timeout", "1", "--", "sh", "-c", "sleep 30 & sleep 30"]).success();
+  let start = std::time::Instant::now();
+  // The output stays open until every process of the command exits
+  let output = process::Command::new(env.bin("slow.py")).output().unwrap();
+  assert_eq!(output.status.code(), Some(124));
+  assert!(start.elapsed().as_secs() < 10);
+}
+
+#[cfg(unix)]
+#[test]
+fn bash_scripts_retry_failed_attempts() {
+  let env = Env::new();
+  env.write("flaky.sh", "echo attempt >> attempts; [ $(wc -l < attempts) -ge 2 ]\n");
+  env.run(&["set", "flaky", "-i", "bash", "-m", "--retries", "2", "--", "sh", "flaky.sh"]).success();
+  let output = process::Command::new(env.bin("flaky")).current_dir(env.root.path()).output().unwrap();
+  assert!(output.status.success());
+  assert_eq!(String::from_utf8_lossy(&output.stderr), "Attempt 1 of 3 failed with code 1\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn stats_summarise_the_execution_log() {
+  let env = Env::new();
+  env.run(&["set", "ok", "--", "true"]).success();
+  env.run(&["set", "bad", "--", "false"]).success();
+  env.run(&["set", "unused", "--", "true"]).success();
+  env.run(&["run", "ok"]).success();
+  assert!(!env.data().join("log").exists());
+  fs::write(env.data().join("config.toml"), "log = true\n").unwrap();
+  env.run(&["run", "ok", "first"]).success();
+  env.run(&["run", "ok", "second"]).success();
+  env.run(&["run", "bad"]).failure().code(13);
+  let log = fs::read_to_string(env.data().join("log")).unwrap();
+  assert_eq!(log.matches("[[run]]").count(), 3);
+  assert!(log.contains("args = [\"second\"]"));
+  env.run(&["stats"]).success().stdout(
+    predicate::str::is_match("^> ok: 2 runs, 0% failed, last run \\ds ago\n> bad: 1 run, 100% failed, last run \\ds ago, last failure \\ds ago\n> unused: never run\n$").unwrap()
+  );
+  env.run(&["stats", "--idle", "1"]).success().stdout("> unused: never run\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn run_via_an_interpreter_uses_its_script() {
+  let env = Env::new();
+  env.write("greet.sh", "echo \"hello $1\"\n");
+  env.run(&["set", "greet", "-s", "greet.sh", "-f", "--", "bash"]).success();
+  assert!(!env.data().join("res").join("greet").join("greet.sh").exists());
+  assert!(!env.bin("greet").exists());
+  env.run(&["run", "greet", "--via", "bash", "world"]).success().stdout("hello world\n");
+  assert!(env.bin("greet").exists());
+  assert!(env.data().join("res").join("greet").join("greet.sh").exists());
+  env.run(&["run", "greet", "--via", "bahs"]).failure().code(4).stderr(predicate::str::contains("bash"));
+  if has_command("python") {
+    env.run(&["run", "greet", "--via", "python", "again"]).success().stdout("hello again\n");
+    assert!(env.bin("greet.py").exists());
+  }
+}
+
+#[cfg(unix)]
+#[test]
+fn resources_are_written_under_the_shortcut() {
+  let env = Env::new();
+  env.write("greeting.txt", "hello");
+  fs::create_dir_all(env.root.path().join("helpers")).unwrap();
+  env.write("helpers/name.txt", "world");
+  env.run(&[
+    "set", "greet", "-r", "greeting.txt", "-r", "helpers", "-i", "bash", "--",
+    "sh", "-c", "echo $(cat \"$0\") $(cat \"$1/name.txt\")", "{res:greeting.txt}", "{res:helpers}"
+  ]).success();
+  env.write("greeting.txt", "changed");
+  env.run(&["make", "greet"]).success();
+  let res = env.data().join("res").join("greet");
+  assert_eq!(fs::read_to_string(res.join("greeting.txt")).unwrap(), "hello");
+  assert_eq!(fs::read_to_string(res.join("helpers").join("name.txt")).unwrap(), "world");
+  env.run(&["run", "greet"]).success().stdout("hello world\n");
+  env.run(&["list"]).success().stdout(predicate::str::contains("{res:greeting.txt} {res:helpers}"));
+  env.run(&["set", "twice", "-r", "greeting.txt", "-r", "greeting.txt", "--", "cat"]).failure().code(6);
+  env.run(&["set", "typo", "--", "cat", "{res:missing}"]).success();
+  env.run(&["doctor"]).failure().stdout(predicate::str::contains("typo: placeholder {res:missing} does not match any resource"));
+}
+
+#[test]
+fn script_backed_shortcuts_write_their_resource() {
+  let env = Env::new();
+  env.write("greet.sh", "echo \"hello $1\"\n");
+  env.run(&["set", "greet", "-s", "greet.sh", "-f", "-i", "bash", "-m", "--", "bash"]).success();
+  let resource = env.data().join("res").join("greet").join("greet.sh");
+  assert_eq!(fs::read_to_string(&resource).unwrap(), "echo \"hello $1\"\n");
+  let script = fs::read_to_string(env.bin("greet")).unwrap();
+  assert!(script.contains(&resource.display().to_string()), "{}", script);
+  env.run(&["run", "greet", "world"]).success().stdout("hello world\n");
+}
+
+#[test]
+fn script_resources_are_kept_per_shortcut() {
+  let env = Env::new();
+  fs::create_dir_all(env.root.path().join("a")).unwrap();
+  fs::create_dir_all(env.root.path().join("b")).unwrap();
+  env.write("a/run.sh", "echo a\n");
+  env.write("b/run.sh", "echo b\n");
+  env.run(&["set", "first", "-s", "a/run.sh", "-f", "-m", "-i", "bash", "--", "bash"]).success();
+  env.run(&["set", "second", "-s", "b/run.sh", "-f", "-m", "-i", "bash", "--", "bash"]).success();
+  env.run(&["run", "first"]).success().stdout("a\n");
+  env.run(&["run", "second"]).success().stdout("b\n");
+  let resource = env.data().join("res").join("first").join("run.sh");
+  env.run(&["list", "-v"]).success().stdout(predicate::str::contains(format!(" |> Resources: {}\n", resource.display())));
+  env.run(&["rename", "first", "renamed"]).success();
+  assert!(!resource.exists());
+  assert!(env.data().join("res").join("renamed").join("run.sh").exists());
+  env.run(&["run", "renamed"]).success().stdout("a\n");
+}
+
+#[test]
+fn migrate_moves_script_resources_to_the_shortcut_directory() {
+  let env = Env::new();
+  env.run(&["list"]).success();
+  let old = env.data().join("res").join("report.py");
+  fs::write(&old, "print('report')\n").unwrap();
+  let template = format!(
+    "version = 1\nname = \"report\"\n\n[body]\ntype = \"CommandWithScript\"\n\n[body.command]\ncmd = [\"python\"]\nscript = '{}'\nbody = \"print('report')\\n\"\n",
+    old.display()
+  );
+  fs::write(env.data().join("meta").join("report.toml"), template).unwrap();
+  env.run(&["migrate"]).success().stdout(predicate::str::contains("Migrated 1 shortcut"));
+  let stored = fs::read_to_string(env.data().join("meta").join("report.toml")).unwrap();
+  let moved = env.data().join("res").join("report").join("report.py");
+  assert!(stored.contains(&moved.display().to_string()), "{}", stored);
+  assert!(env.data().join("backups").join("report.v1.toml").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn bash_scripts_forward_arguments_and_exit_code() {
+  let env = Env::new();
+  env.write("check.sh", "printf '%s|' \"$@\"\nexit 3\n");
+  env.run(&["set", "check", "-s", "check.sh", "-f", "-i", "bash", "-m", "--", "bash"]).success();
+  let output = process::Command::new(env.bin("check")).args(["one", "two words"]).output().unwrap();
+  assert_eq!(String::from_utf8_lossy(&output.stdout), "one|two words|");
+  assert_eq!(output.status.code(), Some(3));
+}
+
+#[cfg(unix)]
+#[test]
+fn python_scripts_forward_arguments_and_exit_code() {
+  if !has_command("python") {
+    eprintln!("python not available, skipping");
+    return
+  }
+  let env = Env::new();
+  env.write("check.sh", "printf '%s|' \"$@\"\nexit 5\n");
+  env.run(&["set", "check", "-s", "check.sh", "-f", "-i", "python", "-m", "--", "bash"]).success();
+  let output = process::Command::new(env.bin("check.py")).args(["one", "two words"]).output().unwrap();
+  assert_eq!(String::from_utf8_lossy(&output.stdout), "one|two words|");
+  assert_eq!(output.status.code(), Some(5));
+}
+
+#[cfg(unix)]
+#[test]
+fn aliases_run_the_same_script() {
+  let env = Env::new();
+  env.run(&["set", "greet", "-a", "hi", "-i", "bash", "-m", "--", "echo", "hello"]).success();
+  let output = process::Command::new(env.bin("hi")).arg("there").output().unwrap();
+  assert_eq!(String::from_utf8_lossy(&output.stdout), "hello there\n");
+}
+
+#[test]
+fn import_refuses_shadowing_aliases_without_force() {
+  let env = Env::new();
+  env.write("aliases", "alias cat='echo meow'\nalias grep='grep --color=auto'\n");
+  env.run(&["import", "--from", "bash-aliases", "aliases"]).success()
+    .stdout(predicate::str::contains("calls itself"))
+    .stderr(predicate::str::contains("shadows").and(predicate::str::contains("use --force")));
+  env.run(&["list"]).success().stdout("");
+  env.run(&["import", "--from", "bash-aliases", "aliases", "--force"]).success();
+  env.run(&["list"]).success().stdout("> cat => echo meow\n");
+}
+
+#[test]
+fn history_failures_have_their_exit_code() {
+  let env = Env::new();
+  env.run(&["undo"]).failure().code(15).stderr(predicate::str::contains("Nothing to undo"));
+  env.run(&["set", "greet", "--", "echo", "hello"]).success();
+  env.run(&["restore", "greet", "--at", "7"]).failure().code(15);
+  env.run(&["restore", "other", "--at", "1"]).failure().code(15);
+}
+
+#[test]
+fn undo_restores_the_replaced_template() {
+  let env = Env::new();
+  env.run(&["set", "greet", "-i", "bash", "-m", "--", "echo", "hello"]).success();
+  env.run(&["set", "greet", "-i", "bash", "-m", "--force", "--", "echo", "bye"]).success();
+  assert!(fs::read_to_string(env.bin("greet")).unwrap().contains("bye"));
+  env.run(&["undo"]).success().stdout("Undone #2 set greet\n");
+  env.run(&["list"]).success().stdout("> greet => echo hello\n");
+  let script = fs::read_to_string(env.bin("greet")).unwrap();
+  assert!(script.contains("hello") && !script.contains("bye"), "{}", script);
+}
+
+#[test]
+fn undo_brings_back_the_unset_template() {
+  let env = Env::new();
+  env.run(&["set", "greet", "-i", "bash", "-m", "--", "echo", "hello"]).success();
+  env.run(&["unset", "greet"]).success();
+  assert!(!env.bin("greet").exists());
+  env.run(&["undo"]).success();
+  env.run(&["list"]).success().stdout("> greet => echo hello\n");
+  assert!(env.bin("greet").exists());
+}
+
+#[test]
+fn undo_reverts_a_rename() {
+  let env = Env::new();
+  env.run(&["set", "greet", "-i", "bash", "-m", "--", "echo", "hello"]).success();
+  env.run(&["rename", "greet", "hi"]).success();
+  assert!(env.bin("hi").exists() && !env.bin("greet").exists());
+  env.run(&["undo"]).success().stdout("Undone #2 rename greet, hi\n");
+  env.run(&["list"]).success().stdout("> greet => echo hello\n");
+  assert!(env.bin("greet").exists());
+  assert!(!env.bin("hi").exists());
+}
+
+#[test]
+fn restore_brings_a_template_back_to_an_older_entry() {
+  let env = Env::new();
+  for word in ["one", "two", "three"] {
+    env.run(&["set", "greet", "-i", "bash", "-m", "--force", "--", "echo", word]).success();
+  }
+  env.run(&["restore", "greet", "--at", "3"]).success();
+  env.run(&["list"]).success().stdout("> greet => echo two\n");
+  env.run(&["restore", "greet", "--at", "2"]).success();
+  env.run(&["list"]).success().stdout("> greet => echo one\n");
+  assert!(fs::read_to_string(env.bin("greet")).unwrap().contains("one"));
+  // The restores are recorded, undoing one goes back to the state it replaced
+  env.run(&["history"]).success().stdout(predicate::str::contains("#5").and(predicate::str::contains("restore greet")));
+  env.run(&["undo"]).success();
+  env.run(&["list"]).success().stdout("> greet => echo two\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn read_only_commands_work_while_locked() {
+  let env = Env::new();
+  env.run(&["set", "greet", "--", "echo", "hello"]).success();
+  // Held by the test process, which is alive so the lock is not stale
+  fs::write(env.data().join(".lock"), process::id().to_string()).unwrap();
+  env.run(&["list"]).success().stdout("> greet => echo hello\n");
+  env.run(&["run", "greet"]).success().stdout("hello\n");
+  env.run(&["set", "bye", "--", "echo", "bye"]).failure().code(11);
+}
+
+#[test]
+fn unset_reports_the_missing_names() {
+  let env = Env::new();
+  env.run(&["set", "greet", "--", "echo", "hello"]).success();
+  env.run(&["unset", "greet", "gone"]).success().stderr(predicate::str::contains("Warning: Shortcut not found: gone"));
+  env.run(&["list"]).success().stdout("");
+}
+
+#[test]
+fn sync_moves_templates_between_data_directories() {
+  if !has_command("git") {
+    return
+  }
+  let remote = TempDir::new().unwrap();
+  process::Command::new("git").args(["init", "-q", "--bare"]).arg(remote.path()).status().unwrap();
+  let remote = remote.path().to_str().unwrap().to_string();
+  let first = Env::new();
+  let second = Env::new();
+  first.write("greet.sh", "echo \"hello $1\"\n");
+  first.run(&["set", "greet", "-s", "greet.sh", "-f", "-i", "bash", "--", "bash"]).success();
+  first.run(&["sync", "pull"]).failure().code(12);
+  first.run(&["sync", "init", &remote]).success();
+  first.run(&["sync", "push"]).success();
+  second.run(&["sync", "init", &remote]).success();
+  second.run(&["sync", "pull"]).success().stdout("Updated 1 shortcut\n");
+  // The pulled template points to the resources of the second data directory
+  let resource = second.data().join("res").join("greet").join("greet.sh");
+  assert_eq!(fs::read_to_string(&resource).unwrap(), "echo \"hello $1\"\n");
+  let script = fs::read_to_string(second.bin("greet")).unwrap();
+  assert!(script.contains(&resource.display().to_string()), "{}", script);
+  second.run(&["run", "greet", "world"]).success().stdout("hello world\n");
+  second.run(&["set", "bye", "-i", "bash", "--", "echo", "bye"]).success();
+  second.run(&["sync", "push"]).success();
+  first.run(&["sync", "pull"]).success().stdout("Updated 1 shortcut\n");
+  assert!(first.bin("bye").exists());
+  first.run(&["list"]).success().stdout(predicate::str::contains("> bye => echo bye"));
+  fs::create_dir_all(first.root.path().join("helpers")).unwrap();
+  first.run(&["set", "copy", "-r", "helpers", "--", "ls", "{res:helpers}"]).success();
+  first.run(&["sync", "push"]).failure().code(7)
+    .stderr(predicate::str::contains("Resource helpers of copy is copied from a directory of this machine"));
+}
+
+#[test]
+fn bundles_embed_the_directory_resources() {
+  let first = Env::new();
+  let second = Env::new();
+  fs::create_dir_all(first.root.path().join("helpers").join("nested")).unwrap();
+  first.write("helpers/a.py", "a");
+  first.write("helpers/nested/b.py", "b");
+  first.run(&["set", "tool", "-r", "helpers", "--", "ls", "{res:helpers}"]).success();
+  let bundle = first.root.path().join("tool.toml");
+  first.run(&["bundle", "export", bundle.to_str().unwrap()]).success();
+  fs::remove_dir_all(first.root.path().join("helpers")).unwrap();
+  second.run(&["bundle", "import", bundle.to_str().unwrap(), "--make"]).success();
+  let resource = second.data().join("res").join("tool").join("helpers");
+  assert_eq!(fs::read_to_string(resource.join("a.py")).unwrap(), "a");
+  assert_eq!(fs::read_to_string(resource.join("nested").join("b.py")).unwrap(), "b");
+}
+
+#[test]
+fn single_file_store_keeps_every_template_in_one_file() {
+  let env = Env::new();
+  fs::create_dir_all(env.data()).unwrap();
+  fs::write(env.data().join("config.toml"), "store = \"single-file\"\n").unwrap();
+  env.run(&["set", "greet", "--", "echo", "hello"]).success();
+  env.run(&["set", "bye", "--", "echo", "bye"]).success();
+  let stored = fs::read_to_string(env.data().join("shortcuts.toml")).unwrap();
+  assert!(stored.contains("[greet]") && stored.contains("[bye]"), "{}", stored);
+  assert!(!env.data().join("meta").exists());
+  env.run(&["list"]).success().stdout("> bye => echo bye\n> greet => echo hello\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn scripts_pass_the_stored_arguments_literally() {
+  let env = Env::new();
+  let args = ["it's", "say \"hi\"", "$HOME", "`id`", "back\\slash"];
+  let mut set = vec!["set", "check", "-i", "bash", "python", "-m", "--", "printf", "%s|"];
+  set.extend(args);
+  env.run(&set).success();
+  let expected = format!("{}|", args.join("|"));
+  let output = process::Command::new(env.bin("check")).output().unwrap();
+  assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+  if has_command("python") {
+    let output = process::Command::new(env.bin("check.py")).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), expected);
+  }
+}
+
+#[test]
+fn bundle_import_refuses_resources_outside_the_shortcut() {
+  let env = Env::new();
+  let bundle = "[[shortcuts]]\nname = \"evil\"\n\n[[shortcuts.resources]]\nname = \"../../../escaped\"\n\
+    path = \"/tmp/escaped\"\ncontent = \"pwned\"\n\n[shortcuts.body]\ntype = \"Command\"\ncommand = [\"true\"]\n";
+  env.write("evil.toml", bundle);
+  env.run(&["bundle", "import", "evil.toml", "--make"]).failure().code(6)
+    .stderr(predicate::str::contains("Name ../../../escaped: resource of evil"));
+  assert!(!env.root.path().join("escaped").exists());
+  assert!(!env.data().join("meta").join("evil.toml").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn edit_saves_and_makes_the_template() {
+  let env = Env::new();
+  env.run(&["set", "greet", "-i", "bash", "--", "echo", "hello"]).success();
+  env.write("editor.sh", "sed -i 's/hello/bye/' \"$1\"\n");
+  env.scu().env("VISUAL", "sh editor.sh").args(["edit", "greet", "-m"]).assert().success();
+  env.run(&["list"]).success().stdout("> greet => echo bye\n");
+  assert!(fs::read_to_string(env.bin("greet")).unwrap().contains("bye"));
+}
+
+#[test]
+fn bundle_import_adds_the_prefix() {
+  let first = Env::new();
+  let second = Env::new();
+  first.run(&["set", "greet", "-a", "hi", "--", "echo", "hello"]).success();
+  let bundle = first.root.path().join("greet.toml");
+  first.run(&["bundle", "export", bundle.to_str().unwrap()]).success();
+  second.run(&["bundle", "import", bundle.to_str().unwrap(), "--prefix", "my-"]).success().stdout("Imported 1 shortcut\n");
+  second.run(&["list"]).success().stdout("> my-greet (my-hi) => echo hello\n");
+  // Existing names are skipped unless overwritten
+  second.run(&["bundle", "import", bundle.to_str().unwrap(), "--prefix", "my-"]).success()
+    .stdout("Skipped existing shortcut: my-greet\nImported 0 shortcuts\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn bundle_import_refuses_shadowing_names_without_force() {
+  let env = Env::new();
+  env.write("ls.toml", "version = 2\n\n[[shortcuts]]\nname = \"ls\"\n\n[shortcuts.body]\ntype = \"Command\"\ncommand = [\"ls\", \"-la\"]\n");
+  env.run(&["bundle", "import", "ls.toml"]).success().stdout("Imported 0 shortcuts\n")
+    .stderr(predicate::str::contains("Name ls: shadows").and(predicate::str::contains("--force")));
+  env.run(&["bundle", "import", "ls.toml", "--force"]).success().stdout("Imported 1 shortcut\n")
+    .stderr(predicate::str::contains("Warning: Name ls: shadows"));
+}
+
+#[test]
+fn bundles_of_newer_versions_are_refused() {
+  let env = Env::new();
+  env.write("new.toml", "version = 99\n\n[[shortcuts]]\nname = \"greet\"\n\n[shortcuts.body]\ntype = \"Command\"\ncommand = [\"echo\"]\n");
+  env.run(&["bundle", "import", "new.toml"]).failure().stderr(predicate::str::contains("newer than the supported version"));
+  env.run(&["list"]).success().stdout("");
+}
@@ -0,0 +1,122 @@
+use std::{env, fs, path};
+
+use scu::{Interpreter, Resource, ResourceSource, Shell, Shortcut, Streams};
+
+/// Set to rewrite the golden files with the current output instead of comparing against them
+const BLESS_VAR: &str = "SCU_BLESS";
+
+fn command(args: &[&str]) -> Shortcut {
+  Shortcut::builder().name("case").command(args.iter().map(|x| x.to_string()).collect()).build()
+}
+
+fn script(args: &[&str], offset: Option<u8>) -> Shortcut {
+  Shortcut::builder().name("case")
+    .command_script(args.iter().map(|x| x.to_string()).collect(), "tool.py", "print('tool')\n", offset).unwrap()
+    .build()
+}
+
+fn redirected(streams: Streams) -> Shortcut {
+  Shortcut::builder().name("case").command(vec!["tool".to_string()]).streams(Some(streams)).build()
+}
+
+fn bounded(timeout: Option<u64>, retries: Option<u32>) -> Shortcut {
+  Shortcut::builder().name("case").command(vec!["fetch".to_string(), "--all".to_string()]).timeout(timeout).retries(retries).build()
+}
+
+fn cases() -> Vec<(&'static str, Shortcut)> {
+  vec![
+    ("plain", command(&["echo", "hello"])),
+    ("no_args", command(&["ls"])),
+    ("spaces", command(&["printf", "%s %s\\n", "two words", "three little words"])),
+    ("quotes", command(&["echo", "it's", "say \"hi\""])),
+    ("binary_with_spaces", command(&["/opt/my tools/run", "--verbose"])),
+    ("script_appended", script(&["python", "-u"], None)),
+    ("script_as_binary", script(&["--flag"], Some(0))),
+    ("script_between", script(&["python", "-X", "dev"], Some(1))),
+    ("redirected", redirected(Streams {
+      stdin: Some("/data/in.txt".into()),
+      stdout: Some("/logs/tool out.log".into()),
+      stderr: Some("/logs/tool.err".into()),
+      ..Streams::default()
+    })),
+    ("redirected_append", redirected(Streams { stdout: Some("/logs/tool.log".into()), append: true, ..Streams::default() })),
+    ("timeout", bounded(Some(30), None)),
+    ("retries", bounded(None, Some(2))),
+    ("timeout_retries_redirected", Shortcut {
+      streams: Some(Streams { stdout: Some("/logs/fetch.log".into()), append: true, ..Streams::default() }),
+      ..bounded(Some(5), Some(3))
+    }),
+    ("resources", Shortcut::builder().name("case")
+      .command(vec!["tool".to_string(), "--config={res:config.toml}".to_string(), "{res:lib}".to_string()])
+      .resources(Some(vec![
+        Resource { name: "config.toml".into(), path: "/res/case/config.toml".into(), source: ResourceSource::Content(String::new()), mode: None },
+        Resource { name: "lib".into(), path: "/res/case/lib".into(), source: ResourceSource::Directory("/src/lib".into()), mode: None }
+      ]))
+      .build()),
+    ("redirected_quiet", redirected(Streams { stderr: Some("/logs/tool.err".into()), quiet: true, ..Streams::default() })),
+    ("redirected_special", redirected(Streams {
+      stdout: Some("/logs/100%^done.log".into()),
+      stderr: Some("/logs/%USERNAME%.err".into()),
+      ..Streams::default()
+    })),
+  ]
+}
+
+fn interpreters() -> Vec<Interpreter> {
+  Interpreter::all().into_iter().chain([Interpreter::Batch]).collect()
+}
+
+/// Shells of `scu export` with the golden file of their definitions
+fn shells() -> Vec<(Shell, &'static str)> {
+  vec![
+    (Shell::Bash, "export_bash"),
+    (Shell::Zsh, "export_zsh"),
+    (Shell::Fish, "export_fish"),
+    (Shell::PowershellProfile, "export_powershell_profile")
+  ]
+}
+
+fn golden_path(case: &str, file: &str) -> path::PathBuf {
+  path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden").join(case).join(file)
+}
+
+/// Compares every rendered output with its golden file, or rewrites them when blessing
+fn assert_golden(kind: &str, rendered: impl IntoIterator<Item = (path::PathBuf, String)>) {
+  let bless = env::var_os(BLESS_VAR).is_some();
+  let mut mismatches = Vec::new();
+  for (path, rendered) in rendered {
+    if bless {
+      fs::create_dir_all(path.parent().unwrap()).unwrap();
+      fs::write(&path, &rendered).unwrap();
+      continue
+    }
+    match fs::read_to_string(&path) {
+      Ok(golden) if golden.replace("\r\n", "\n") == rendered => {},
+      Ok(golden) => mismatches.push(format!("{}\n--- golden\n{}--- rendered\n{}", path.display(), golden, rendered)),
+      Err(_) => mismatches.push(format!("{}: missing golden file", path.display()))
+    }
+  }
+  assert!(
+    mismatches.is_empty(),
+    "{} {} differ from their golden files, run with {}=1 to update them:\n\n{}",
+    mismatches.len(), kind, BLESS_VAR, mismatches.join("\n")
+  );
+}
+
+#[test]
+fn templates_match_golden_files() {
+  let rendered = cases().into_iter().flat_map(|(case, shortcut)| interpreters().into_iter().map(move |interpreter| (
+    golden_path(case, interpreter.name()),
+    format!("{}\n", shortcut.script(&interpreter).unwrap())
+  )));
+  assert_golden("templates", rendered);
+}
+
+#[test]
+fn definitions_match_golden_files() {
+  let rendered = cases().into_iter().flat_map(|(case, shortcut)| shells().into_iter().map(move |(shell, file)| (
+    golden_path(case, file),
+    format!("{}\n", shortcut.definition(&shell, &shortcut.name).unwrap())
+  )));
+  assert_golden("definitions", rendered);
+}